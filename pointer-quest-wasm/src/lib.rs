@@ -1,7 +1,37 @@
 use wasm_bindgen::prelude::*;
 use web_sys::{console, window, CanvasRenderingContext2d, HtmlCanvasElement};
 use std::f64;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+// Tipo de puntero o referencia que representa una flecha
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PointerKind {
+    #[default]
+    Raw,
+    Unique,
+    Shared,
+    Weak,
+    Borrow,
+    MutBorrow,
+    Reference, // referencia de C++ (`int& r = x;`): alias que no se puede reasignar
+}
+
+impl PointerKind {
+    // Accesos que permiten escribir en la memoria apuntada
+    fn writes(self) -> bool {
+        matches!(self, PointerKind::MutBorrow)
+    }
+
+    // Punteros que cuentan para mantener vivo su destino (los weak no)
+    fn owns(self) -> bool {
+        matches!(self, PointerKind::Unique | PointerKind::Shared)
+    }
+
+    // Las referencias quedan ligadas a su destino para siempre
+    fn rebindable(self) -> bool {
+        !matches!(self, PointerKind::Reference)
+    }
+}
 
 // Estructura para representar un puntero en 3D
 #[wasm_bindgen]
@@ -18,11 +48,162 @@ pub struct Pointer3D {
     thickness: f64,
     animated: bool,
     animation_progress: f64,
+    #[serde(default)]
+    target_block_id: Option<String>,
+    #[serde(default = "default_true")]
+    visible: bool,
+    #[serde(default)]
+    target_address: Option<u64>,
+    // Bits de etiqueta guardados en los bits bajos de la dirección
+    #[serde(default)]
+    tag_bits: Option<u8>,
+    // Número de bits bajos reservados para la etiqueta; si falta, se deduce de la alineación (stride_bytes)
+    #[serde(default)]
+    tag_width: Option<u8>,
+    // Rango apuntado dentro del bloque destino: (desplazamiento, longitud) en bytes
+    #[serde(default)]
+    target_range: Option<(u64, u64)>,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    kind: PointerKind,
+    // Tipo declarado en el origen (p. ej. "int*") y tipo con el que se ve en el destino (p. ej. "void*")
+    #[serde(default)]
+    type_name: Option<String>,
+    #[serde(default)]
+    target_type_name: Option<String>,
+    #[serde(default)]
+    is_null: bool,
+    // Declarado pero aún sin destino (`int* p;`)
+    #[serde(default)]
+    pending: bool,
+    // sizeof(T) del tipo apuntado; lo usa animate_pointer_arithmetic para calcular los saltos
+    #[serde(default)]
+    stride_bytes: Option<u32>,
+    // Variable (bloque) que guarda el puntero; si está, el inicio sigue a su ancla
+    #[serde(default)]
+    source_block_id: Option<String>,
+    // Fijado tal y como se creó: no lo cambian reasignaciones ni animaciones
+    #[serde(default)]
+    pinned: bool,
+    // Peso de la arista en grafos ponderados
+    #[serde(default)]
+    weight: Option<f64>,
+    // Punto de control de una curva cuadrática ajustada a mano; None dibuja una recta
+    #[serde(default)]
+    control_point: Option<(f64, f64, f64)>,
+}
+
+// Error de una comprobación interna; se convierte en el JsValue con su mensaje al volver a JS
+#[derive(Debug, PartialEq)]
+struct EngineError(String);
+
+impl From<EngineError> for JsValue {
+    fn from(error: EngineError) -> JsValue {
+        JsValue::from_str(&error.0)
+    }
+}
+
+// Reloj del navegador en ms
+fn performance_now() -> f64 {
+    window().unwrap().performance().unwrap().now()
+}
+
+// Serializa a JS con objetos planos en lugar de Map (compatible con JSON)
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for Pointer3D {
+    fn default() -> Self {
+        Pointer3D {
+            id: String::new(),
+            start_x: 0.0,
+            start_y: 0.0,
+            start_z: 0.0,
+            end_x: 0.0,
+            end_y: 0.0,
+            end_z: 0.0,
+            color: String::new(),
+            thickness: 3.0,
+            animated: false,
+            animation_progress: 0.0,
+            target_block_id: None,
+            visible: true,
+            target_address: None,
+            tag_bits: None,
+            tag_width: None,
+            target_range: None,
+            locked: false,
+            kind: PointerKind::Raw,
+            type_name: None,
+            target_type_name: None,
+            is_null: false,
+            pending: false,
+            stride_bytes: None,
+            source_block_id: None,
+            pinned: false,
+            weight: None,
+            control_point: None,
+        }
+    }
+}
+
+// Coordenadas y tamaños finitos: NaN/Infinity rompen las llamadas al canvas
+fn pointer_is_finite(pointer: &Pointer3D) -> bool {
+    [pointer.start_x, pointer.start_y, pointer.start_z, pointer.end_x, pointer.end_y, pointer.end_z, pointer.thickness]
+        .iter()
+        .all(|v| v.is_finite())
+}
+
+fn block_is_finite(block: &MemoryBlock3D) -> bool {
+    [block.x, block.y, block.z, block.width, block.height, block.depth].iter().all(|v| v.is_finite())
+}
+
+fn control_point_is_finite(pointer: &Pointer3D) -> bool {
+    pointer.control_point.is_none_or(|(x, y, z)| x.is_finite() && y.is_finite() && z.is_finite())
+}
+
+// Error de importación con los ids de los objetos con coordenadas no finitas
+fn ensure_finite<'a>(pointers: impl Iterator<Item = &'a Pointer3D>, blocks: impl Iterator<Item = &'a MemoryBlock3D>) -> Result<(), EngineError> {
+    let mut offending: Vec<&str> = pointers
+        .filter(|p| !pointer_is_finite(p) || !control_point_is_finite(p))
+        .map(|p| p.id.as_str())
+        .chain(blocks.filter(|b| !block_is_finite(b)).map(|b| b.id.as_str()))
+        .collect();
+    if offending.is_empty() {
+        return Ok(());
+    }
+    offending.sort();
+    Err(EngineError(format!("Non-finite coordinates in: {}", offending.join(", "))))
+}
+
+// Máscara de los `width` bits bajos que ocupa una etiqueta (2 -> 0b11); 64 o más cubre la dirección entera,
+// ya que tag_width puede llegar sin validar desde add_pointer o import_scene
+fn tag_mask(width: u32) -> u64 {
+    1u64.checked_shl(width).map_or(u64::MAX, |bit| bit - 1)
+}
+
+// Bits reservados para la etiqueta: los indicados o los que deja libres la alineación del tipo apuntado
+fn tag_width_of(pointer: &Pointer3D) -> Option<u32> {
+    resolve_tag_width(pointer.tag_width, pointer.stride_bytes)
+}
+
+fn resolve_tag_width(tag_width: Option<u8>, stride_bytes: Option<u32>) -> Option<u32> {
+    tag_width.map(u32::from).or_else(|| {
+        stride_bytes
+            .filter(|stride| stride.is_power_of_two() && *stride > 1)
+            .map(|stride| stride.trailing_zeros())
+    })
 }
 
 // Estructura para representar un bloque de memoria en 3D
 #[wasm_bindgen]
-#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct MemoryBlock3D {
     id: String,
     x: f64,
@@ -34,220 +215,7283 @@ pub struct MemoryBlock3D {
     color: String,
     value: Option<String>,
     memory_type: String, // "stack", "heap", "global"
+    #[serde(default)]
+    text_color: Option<String>, // si falta, se elige según el contraste con el relleno
+    #[serde(default)]
+    ref_count: Option<u32>,
+    #[serde(default)]
+    allocated_at: Option<f64>, // instante de reserva en ms según el reloj del motor
+    #[serde(default)]
+    freed_at: Option<f64>, // instante de liberación; None mientras siga vivo
+    #[serde(default)]
+    address: Option<u64>,
+    #[serde(default)]
+    size_bytes: Option<u32>,
+    #[serde(default)]
+    locked: bool,
+    #[serde(default)]
+    frame_id: Option<String>, // marco de pila al que pertenece el bloque
+    #[serde(default)]
+    region_id: Option<String>, // región o arena del asignador que contiene el bloque
+    #[serde(default)]
+    read_only: bool, // memoria const / de solo lectura
+    #[serde(default)]
+    moved_from: bool, // su valor se transfirió con una semántica de movimiento
+    #[serde(default)]
+    type_name: Option<String>, // tipo concreto del valor (p. ej. "int")
+    #[serde(default)]
+    type_param: Option<String>, // parámetro genérico sin resolver ("T"); vacío se muestra como "?"
 }
 
-// Motor de animaciones principal
-#[wasm_bindgen]
-pub struct AnimationEngine {
-    pointers: HashMap<String, Pointer3D>,
-    memory_blocks: HashMap<String, MemoryBlock3D>,
-    animation_speed: f64,
-    last_frame_time: f64,
+// Convierte un color CSS sencillo (#rgb, #rrggbb, rgb()/rgba() o nombre básico) a RGB
+fn parse_color(color: &str) -> Option<(u8, u8, u8)> {
+    let color = color.trim().to_ascii_lowercase();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<_>>()?;
+        return match digits.len() {
+            3 => Some((digits[0] * 17, digits[1] * 17, digits[2] * 17)),
+            6 => Some((digits[0] * 16 + digits[1], digits[2] * 16 + digits[3], digits[4] * 16 + digits[5])),
+            _ => None,
+        };
+    }
+
+    if let Some(args) = color
+        .strip_prefix("rgba(")
+        .or_else(|| color.strip_prefix("rgb("))
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = args
+            .split(',')
+            .take(3)
+            .map(|c| c.trim().parse::<f64>().ok().map(|v| v.clamp(0.0, 255.0) as u8))
+            .collect::<Option<_>>()?;
+        return (channels.len() == 3).then(|| (channels[0], channels[1], channels[2]));
+    }
+
+    match color.as_str() {
+        "white" => Some((255, 255, 255)),
+        "black" => Some((0, 0, 0)),
+        "red" => Some((255, 0, 0)),
+        "green" => Some((0, 128, 0)),
+        "blue" => Some((0, 0, 255)),
+        "yellow" => Some((255, 255, 0)),
+        "orange" => Some((255, 165, 0)),
+        "purple" => Some((128, 0, 128)),
+        "gray" | "grey" => Some((128, 128, 128)),
+        _ => None,
+    }
 }
 
-#[wasm_bindgen]
-impl AnimationEngine {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> AnimationEngine {
-        console::log_1(&"AnimationEngine initialized".into());
+// Aclara (factor > 1) u oscurece (factor < 1) un color; si no se reconoce se devuelve tal cual
+fn shade_color(color: &str, factor: f64) -> String {
+    match parse_color(color) {
+        Some((r, g, b)) => {
+            let scale = |c: u8| (c as f64 * factor).round().clamp(0.0, 255.0) as u8;
+            format!("rgb({}, {}, {})", scale(r), scale(g), scale(b))
+        }
+        None => color.to_string(),
+    }
+}
 
-        AnimationEngine {
-            pointers: HashMap::new(),
-            memory_blocks: HashMap::new(),
-            animation_speed: 1.0,
-            last_frame_time: window().unwrap().performance().unwrap().now(),
+// Tono (grados), saturación y luminosidad (0-1) a #rrggbb
+fn hsl_to_hex(hue: f64, saturation: f64, lightness: f64) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |c: f64| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+// Luminancia relativa (WCAG) de un color RGB, entre 0 y 1
+fn relative_luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let channel = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
         }
+    };
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+// Color de texto legible (oscuro o claro) sobre un relleno dado
+fn contrasting_text_color(fill: &str) -> &'static str {
+    match parse_color(fill) {
+        Some(rgb) if relative_luminance(rgb) > 0.179 => "#111111",
+        _ => "white",
     }
+}
 
-    #[wasm_bindgen]
-    pub fn add_pointer(&mut self, pointer: JsValue) {
-        let ptr: Pointer3D = serde_wasm_bindgen::from_value(pointer).unwrap();
-        self.pointers.insert(ptr.id.clone(), ptr);
+// Duración del destello al instanciar un parámetro de tipo
+const TYPE_INSTANTIATION_FLASH_MS: f64 = 400.0;
+
+// Extremos de las dos aletas de una punta de flecha en (x, y) que llega desde (from_x, from_y)
+fn arrow_head_points(x: f64, y: f64, from_x: f64, from_y: f64) -> [(f64, f64); 2] {
+    let angle = (y - from_y).atan2(x - from_x);
+    let arrow_length = 15.0;
+    [angle + f64::consts::PI / 6.0, angle - f64::consts::PI / 6.0]
+        .map(|wing| (x - arrow_length * wing.cos(), y - arrow_length * wing.sin()))
+}
+
+// Punto en t = 0.5 de la curva cuadrática (o de la recta si no hay punto de control)
+fn curve_midpoint(start: (f64, f64), control: Option<(f64, f64)>, end: (f64, f64)) -> (f64, f64) {
+    let (cx, cy) = control.unwrap_or(((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0));
+    (0.25 * start.0 + 0.5 * cx + 0.25 * end.0, 0.25 * start.1 + 0.5 * cy + 0.25 * end.1)
+}
+
+// Extensión de un texto con la fuente dada, medida en el canvas
+fn measure_text_extent(context: &CanvasRenderingContext2d, text: &str, font: &str) -> Result<TextExtent, JsValue> {
+    context.set_font(font);
+    let metrics = context.measure_text(text)?;
+    Ok(TextExtent {
+        left: metrics.actual_bounding_box_left(),
+        right: metrics.width().max(metrics.actual_bounding_box_right()),
+        ascent: metrics.actual_bounding_box_ascent(),
+        descent: metrics.actual_bounding_box_descent(),
+    })
+}
+
+// Color del texto de la etiqueta de un bloque
+fn block_label_color(block: &MemoryBlock3D) -> &str {
+    if block.moved_from {
+        "#D5D8DC"
+    } else {
+        block.text_color.as_deref().unwrap_or_else(|| contrasting_text_color(&block.color))
     }
+}
 
-    #[wasm_bindgen]
-    pub fn add_memory_block(&mut self, block: JsValue) {
-        let mem_block: MemoryBlock3D = serde_wasm_bindgen::from_value(block).unwrap();
-        self.memory_blocks.insert(mem_block.id.clone(), mem_block);
+// Color y grosor del borde de un bloque (distinto para la memoria de solo lectura)
+fn block_border(block: &MemoryBlock3D) -> (&'static str, f64) {
+    if block.read_only {
+        ("#85C1E9", 3.0)
+    } else {
+        ("white", 2.0)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn remove_pointer(&mut self, id: &str) {
-        self.pointers.remove(id);
+// Las dos líneas paralelas con las que se dibuja una referencia entre start y end
+fn reference_binding_lines(pointer: &Pointer3D, start: (f64, f64), end: (f64, f64)) -> [((f64, f64), (f64, f64)); 2] {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+    let gap = pointer.thickness.max(2.0);
+    let (nx, ny) = (-dy / length * gap, dx / length * gap);
+    [-1.0, 1.0].map(|side| ((start.0 + nx * side, start.1 + ny * side), (end.0 + nx * side, end.1 + ny * side)))
+}
+
+fn reference_line_width(pointer: &Pointer3D) -> f64 {
+    (pointer.thickness / 2.0).max(1.0)
+}
+
+// Atributos SVG equivalentes a una fuente abreviada de canvas ("italic 14px Arial")
+fn svg_font_attributes(font: &str) -> String {
+    let mut attributes = Vec::new();
+    let mut family = Vec::new();
+    for token in font.split_whitespace() {
+        match token {
+            "italic" | "oblique" => attributes.push(format!("font-style=\"{}\"", token)),
+            "bold" | "bolder" | "lighter" => attributes.push(format!("font-weight=\"{}\"", token)),
+            _ => match token.strip_suffix("px").filter(|size| size.parse::<f64>().is_ok()) {
+                Some(size) => attributes.push(format!("font-size=\"{}\"", size)),
+                None => family.push(token),
+            },
+        }
+    }
+    if !family.is_empty() {
+        attributes.push(format!("font-family=\"{}\"", xml_escape(&family.join(" "))));
     }
+    attributes.join(" ")
+}
 
-    #[wasm_bindgen]
-    pub fn remove_memory_block(&mut self, id: &str) {
-        self.memory_blocks.remove(id);
+// Escapa texto para incrustarlo en XML/SVG
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Título del diagrama: tamaño de fuente y margen respecto al borde del canvas
+const TITLE_FONT_SIZE: f64 = 20.0;
+const TITLE_MARGIN: f64 = 16.0;
+
+fn title_font() -> String {
+    format!("bold {}px Arial", TITLE_FONT_SIZE)
+}
+
+// Posición del título en el canvas
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TitlePosition {
+    Top,
+    TopLeft,
+    TopRight,
+    Bottom,
+    BottomLeft,
+    BottomRight,
+}
+
+impl TitlePosition {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "top" => TitlePosition::Top,
+            "top-left" => TitlePosition::TopLeft,
+            "top-right" => TitlePosition::TopRight,
+            "bottom" => TitlePosition::Bottom,
+            "bottom-left" => TitlePosition::BottomLeft,
+            "bottom-right" => TitlePosition::BottomRight,
+            _ => return None,
+        })
     }
 
-    #[wasm_bindgen]
-    pub fn update_pointer_position(&mut self, id: &str, end_x: f64, end_y: f64, end_z: f64) {
-        if let Some(pointer) = self.pointers.get_mut(id) {
-            pointer.end_x = end_x;
-            pointer.end_y = end_y;
-            pointer.end_z = end_z;
-        }
+    // Punto (línea base) y alineación del texto ("left", "center" o "right") en un canvas de width x height
+    fn anchor(self, width: f64, height: f64) -> (f64, f64, &'static str) {
+        let (x, align) = match self {
+            TitlePosition::TopLeft | TitlePosition::BottomLeft => (TITLE_MARGIN, "left"),
+            TitlePosition::Top | TitlePosition::Bottom => (width / 2.0, "center"),
+            TitlePosition::TopRight | TitlePosition::BottomRight => (width - TITLE_MARGIN, "right"),
+        };
+        let y = match self {
+            TitlePosition::Top | TitlePosition::TopLeft | TitlePosition::TopRight => TITLE_MARGIN + TITLE_FONT_SIZE,
+            _ => height - TITLE_MARGIN,
+        };
+        (x, y, align)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn animate(&mut self, delta_time: f64) {
-        let current_time = window().unwrap().performance().unwrap().now();
-        let actual_delta = (current_time - self.last_frame_time) / 1000.0;
-        self.last_frame_time = current_time;
+// Posición de la etiqueta de un bloque respecto a su esquina superior izquierda (línea base)
+const BLOCK_LABEL_OFFSET: (f64, f64) = (10.0, 25.0);
 
-        // Animar punteros
-        for pointer in self.pointers.values_mut() {
-            if pointer.animated {
-                pointer.animation_progress += actual_delta * self.animation_speed;
+// Texto y fuente de la etiqueta de un bloque, si la tiene
+fn block_label(block: &MemoryBlock3D) -> Option<(&str, &'static str)> {
+    if block.moved_from {
+        Some(("moved", "italic 14px Arial"))
+    } else if let Some(ref param) = block.type_param {
+        Some((if param.is_empty() { "?" } else { param }, "bold 20px Arial"))
+    } else {
+        block.value.as_deref().map(|value| (value, "14px Arial"))
+    }
+}
 
-                if pointer.animation_progress > 1.0 {
-                    pointer.animation_progress = 0.0;
-                }
+// Punto de anclaje de un bloque (centro de su cara frontal) en coordenadas de mundo
+fn block_anchor(block: &MemoryBlock3D) -> (f64, f64, f64) {
+    (block.x + block.width / 2.0, block.y + block.height / 2.0, block.z)
+}
 
-                // Efecto de pulso para punteros animados
-                let pulse = (pointer.animation_progress * f64::consts::PI * 2.0).sin();
-                pointer.thickness = 3.0 + pulse * 2.0;
-            }
-        }
+// Caras superior/inferior y laterales visibles de una caja extruida, con su color de sombreado
+fn box_faces(block: &MemoryBlock3D, projection: &ProjectionMatrix) -> Vec<([(f64, f64); 4], String)> {
+    // Las ocho esquinas pasan por la matriz activa: corner(i, j, k) con i, j, k en {0, 1}
+    let corner = |i: usize, j: usize, k: usize| {
+        apply_projection(
+            projection,
+            block.x + i as f64 * block.width,
+            block.y + j as f64 * block.height,
+            block.z + k as f64 * block.depth,
+        )
+    };
+
+    // Caras laterales con orientación hacia fuera; son visibles las que, proyectadas, giran
+    // en el mismo sentido que la cara frontal
+    let front = signed_area(&[corner(0, 0, 0), corner(0, 1, 0), corner(1, 1, 0), corner(1, 0, 0)]);
+    let top_color = shade_color(&block.color, 1.25);
+    let side_color = shade_color(&block.color, 0.7);
+    let faces = [
+        ([corner(0, 0, 0), corner(1, 0, 0), corner(1, 0, 1), corner(0, 0, 1)], &top_color),
+        ([corner(0, 1, 0), corner(0, 1, 1), corner(1, 1, 1), corner(1, 1, 0)], &top_color),
+        ([corner(0, 0, 0), corner(0, 0, 1), corner(0, 1, 1), corner(0, 1, 0)], &side_color),
+        ([corner(1, 0, 0), corner(1, 1, 0), corner(1, 1, 1), corner(1, 0, 1)], &side_color),
+    ];
+    faces
+        .into_iter()
+        .filter(|(corners, _)| signed_area(corners) * front > 0.0)
+        .map(|(corners, color)| (corners, color.clone()))
+        .collect()
+}
+
+// Zona fuera de límites pegada al borde derecho de un array de `slots` posiciones válidas
+fn danger_zone_rect((x, y, width, height): (f64, f64, f64, f64), slots: usize) -> (f64, f64, f64, f64) {
+    let slot_width = width / slots as f64;
+    (x + width, y, slot_width * DANGER_ZONE_SLOTS, height)
+}
+
+// Tipos de origen y destino de un puntero cuando difieren (conversión implícita, p. ej. int* → void*)
+fn type_conversion(pointer: &Pointer3D) -> Option<(&str, &str)> {
+    match (pointer.type_name.as_deref(), pointer.target_type_name.as_deref()) {
+        (Some(from), Some(to)) if from != to => Some((from, to)),
+        _ => None,
     }
+}
 
-    #[wasm_bindgen]
-    pub fn render(&self, canvas_id: &str) -> Result<(), JsValue> {
-        let window = web_sys::window().unwrap();
-        let document = window.document().unwrap();
-        let canvas = document
-            .get_element_by_id(canvas_id)
-            .unwrap()
-            .dyn_into::<HtmlCanvasElement>()?;
+// Rectángulo de un bloque a través de una proyección: (x, y, ancho, alto)
+fn projected_rect(projection: &ProjectionMatrix, block: &MemoryBlock3D) -> (f64, f64, f64, f64) {
+    let (x0, y0) = apply_projection(projection, block.x, block.y, block.z);
+    let (x1, y1) = apply_projection(projection, block.x + block.width, block.y + block.height, block.z);
+    (x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs())
+}
 
-        let context = canvas
-            .get_context("2d")?
-            .unwrap()
-            .dyn_into::<CanvasRenderingContext2d>()?;
+// Área con signo de un polígono (fórmula del lazada); el signo indica el sentido de giro
+fn signed_area(points: &[(f64, f64)]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % n];
+            x1 * y2 - x2 * y1
+        })
+        .sum::<f64>()
+        / 2.0
+}
 
-        // Limpiar canvas
-        context.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+// Distancia de un punto a un rectángulo (0 si está dentro)
+fn distance_to_rect(px: f64, py: f64, rect: (f64, f64, f64, f64)) -> f64 {
+    let (x, y, w, h) = rect;
+    let dx = (x - px).max(0.0).max(px - (x + w));
+    let dy = (y - py).max(0.0).max(py - (y + h));
+    (dx * dx + dy * dy).sqrt()
+}
 
-        // Renderizar bloques de memoria
-        for block in self.memory_blocks.values() {
-            self.render_memory_block(&context, block);
-        }
+// Indica si dos rectángulos (x, y, ancho, alto) se solapan
+fn rects_overlap(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+}
 
-        // Renderizar punteros
-        for pointer in self.pointers.values() {
-            self.render_pointer(&context, pointer);
-        }
+#[derive(serde::Serialize)]
+struct Point2D {
+    x: f64,
+    y: f64,
+}
 
-        Ok(())
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct ScreenRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+// Extensión de un texto medido desde su punto de inicio y su línea base (como TextMetrics)
+struct TextExtent {
+    left: f64,
+    right: f64,
+    ascent: f64,
+    descent: f64,
+}
+
+// Tamaño de canvas (píxeles CSS) y desplazamiento para mostrar la escena entera a escala 1:1
+#[derive(serde::Serialize)]
+struct CanvasSize {
+    width: f64,
+    height: f64,
+    pan_x: f64,
+    pan_y: f64,
+}
+
+// Acceso de escritura sobre un bloque de solo lectura
+#[derive(serde::Serialize)]
+struct ConstWriteViolation {
+    pointer_id: String,
+    block_id: String,
+}
+
+// Puntero guardado en una clase de memoria que apunta a otra (p. ej. "stack→heap")
+#[derive(serde::Serialize)]
+struct StorageCrossing {
+    pointer_id: String,
+    storage_block_id: String,
+    target_block_id: String,
+    crossing: String,
+}
+
+// Nodo que rompe la estructura de árbol: "shared" (varios caminos) o "cycle" (vuelta a un ancestro)
+#[derive(serde::Serialize)]
+struct TreeViolation {
+    kind: &'static str,
+    block_id: String,
+    pointer_ids: Vec<String>,
+}
+
+// Gravedad de un diagnóstico; el orden de las variantes es el de presentación
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+// Problema detectado por alguno de los validadores
+#[derive(serde::Serialize)]
+struct Diagnostic {
+    severity: Severity,
+    code: &'static str,
+    object_ids: Vec<String>,
+    message: String,
+}
+
+// Puntero guardado en una región que apunta a otra
+#[derive(serde::Serialize)]
+struct RegionCrossing {
+    pointer_id: String,
+    source_region: String,
+    target_region: String,
+}
+
+// Puntero que apunta al bloque que lo contiene, o referencia ligada a sí misma
+#[derive(serde::Serialize)]
+struct SelfReference {
+    pointer_id: String,
+    block_id: String,
+    kind: &'static str, // "pointer" o "reference"
+}
+
+// Tamaño y colores por defecto de los objetos creados desde CSV
+const CSV_BLOCK_WIDTH: f64 = 120.0;
+const CSV_BLOCK_HEIGHT: f64 = 60.0;
+const CSV_POINTER_COLOR: &str = "#F1C40F";
+
+fn default_block_color(memory_type: &str) -> &'static str {
+    match memory_type {
+        "stack" => "#3498DB",
+        "heap" => "#E67E22",
+        "global" => "#9B59B6",
+        _ => "#7F8C8D",
     }
+}
+
+// Filas de un CSV sencillo (sin comillas) con su número de línea; omite la cabecera y líneas vacías
+fn csv_rows(csv: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    csv.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.split(',').map(str::trim).collect::<Vec<_>>()))
+        .filter(|(_, fields)| !(fields.len() == 1 && fields[0].is_empty()))
+        .filter(|(row, fields)| !(*row == 1 && fields[0].eq_ignore_ascii_case("id")))
+}
 
-    fn render_memory_block(&self, context: &CanvasRenderingContext2d, block: &MemoryBlock3D) {
-        // Proyección simple 3D a 2D (isométrica)
-        let iso_x = block.x - block.z * 0.5;
-        let iso_y = block.y - block.z * 0.5;
+// Efectos previstos de liberar un bloque
+#[derive(serde::Serialize)]
+struct FreePreview {
+    dangling: Vec<String>,
+    leaked: Vec<String>,
+}
 
-        // Dibujar bloque como un rectángulo con perspectiva
-        context.set_fill_style(&JsValue::from_str(&block.color));
-        context.fill_rect(iso_x, iso_y, block.width, block.height);
+// Resumen de la escena para paneles
+#[derive(serde::Serialize)]
+struct SceneStats {
+    blocks_by_type: BTreeMap<String, usize>,
+    pointers_by_kind: BTreeMap<String, usize>,
+    dangling_pointers: usize,
+    null_pointers: usize,
+    leaked_blocks: usize,
+    total_allocated_bytes: u64,
+    stack_depth: usize,
+}
 
-        // Dibujar borde
-        context.set_stroke_style(&JsValue::from_str("white"));
-        context.set_line_width(2.0);
-        context.stroke_rect(iso_x, iso_y, block.width, block.height);
+// Métricas del grafo de punteros
+#[derive(serde::Serialize)]
+struct BlockDegree {
+    in_degree: usize,
+    out_degree: usize,
+}
 
-        // Dibujar valor si existe
-        if let Some(ref value) = block.value {
-            context.set_fill_style(&JsValue::from_str("white"));
-            context.set_font("14px Arial");
-            context.fill_text(value, iso_x + 10.0, iso_y + 25.0).unwrap();
-        }
+#[derive(serde::Serialize)]
+struct GraphMetrics {
+    blocks: BTreeMap<String, BlockDegree>,
+    pointers: BTreeMap<String, usize>,
+    roots: usize,
+    leaves: usize,
+}
+
+// Compara valores mostrando primero los numéricos (por número) y luego el texto
+fn compare_values(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
     }
+}
 
-    fn render_pointer(&self, context: &CanvasRenderingContext2d, pointer: &Pointer3D) {
-        // Proyección simple 3D a 2D
-        let start_iso_x = pointer.start_x - pointer.start_z * 0.5;
-        let start_iso_y = pointer.start_y - pointer.start_z * 0.5;
-        let end_iso_x = pointer.end_x - pointer.end_z * 0.5;
-        let end_iso_y = pointer.end_y - pointer.end_z * 0.5;
+// Separación mínima entre un bloque nuevo y los existentes
+const FREE_SLOT_GAP: f64 = 10.0;
 
-        // Dibujar línea del puntero
-        context.set_stroke_style(&JsValue::from_str(&pointer.color));
-        context.set_line_width(pointer.thickness);
-        context.begin_path();
-        context.move_to(start_iso_x, start_iso_y);
-        context.line_to(end_iso_x, end_iso_y);
-        context.stroke();
+// Curvas de aceleración para las transiciones
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+        }
+    }
+}
+
+// Pares (t, eased_t) repartidos uniformemente en [0, 1] para dibujar la curva de una aceleración
+#[wasm_bindgen]
+pub fn sample_easing(kind: Easing, samples: usize) -> Result<JsValue, JsValue> {
+    if samples < 2 {
+        return Err(JsValue::from_str(&format!("Need at least 2 samples, got {}", samples)));
+    }
+    let points: Vec<(f64, f64)> = (0..samples)
+        .map(|i| {
+            let t = i as f64 / (samples - 1) as f64;
+            (t, kind.apply(t))
+        })
+        .collect();
+    to_js(&points)
+}
 
-        // Dibujar cabeza de flecha
-        self.draw_arrow_head(&context, end_iso_x, end_iso_y, start_iso_x, start_iso_y);
+// Velocidad de animación admitida; NaN cae al mínimo para no contagiar a los tweens
+fn clamp_animation_speed(speed: f64) -> f64 {
+    if speed.is_nan() {
+        0.1
+    } else {
+        speed.clamp(0.1, 5.0)
     }
+}
 
-    fn draw_arrow_head(&self, context: &CanvasRenderingContext2d, x: f64, y: f64, from_x: f64, from_y: f64) {
-        let angle = (y - from_y).atan2(x - from_x);
-        let arrow_length = 15.0;
+// Rampa suave de la velocidad global de animación
+#[derive(Clone, Copy, Debug)]
+struct SpeedRamp {
+    from: f64,
+    to: f64,
+    elapsed_ms: f64,
+    duration_ms: f64,
+}
 
-        let x1 = x - arrow_length * (angle + f64::consts::PI / 6.0).cos();
-        let y1 = y - arrow_length * (angle + f64::consts::PI / 6.0).sin();
-        let x2 = x - arrow_length * (angle - f64::consts::PI / 6.0).cos();
-        let y2 = y - arrow_length * (angle - f64::consts::PI / 6.0).sin();
+// Interpolación del valor numérico mostrado en un bloque
+#[derive(Clone, Debug)]
+struct ValueTween {
+    from: f64,
+    to: f64,
+    elapsed_ms: f64,
+    duration_ms: f64,
+    decimals: usize,
+}
 
-        context.begin_path();
-        context.move_to(x, y);
-        context.line_to(x1, y1);
-        context.move_to(x, y);
-        context.line_to(x2, y2);
-        context.stroke();
+impl ValueTween {
+    fn current(&self) -> f64 {
+        let t = (self.elapsed_ms / self.duration_ms).min(1.0);
+        self.from + (self.to - self.from) * t
     }
 
-    #[wasm_bindgen]
-    pub fn get_pointer_count(&self) -> usize {
-        self.pointers.len()
+    fn format(&self, value: f64) -> String {
+        format!("{:.*}", self.decimals, value)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn get_memory_block_count(&self) -> usize {
-        self.memory_blocks.len()
+// Transición del color de un objeto, interpolada en RGB
+#[derive(Clone, Debug)]
+struct ColorTween {
+    from: (u8, u8, u8),
+    to: (u8, u8, u8),
+    target: String, // color final tal y como se pidió
+    elapsed_ms: f64,
+    duration_ms: f64,
+}
+
+impl ColorTween {
+    fn current(&self) -> String {
+        let t = (self.elapsed_ms / self.duration_ms).min(1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        format!("rgb({}, {}, {})", mix(self.from.0, self.to.0), mix(self.from.1, self.to.1), mix(self.from.2, self.to.2))
     }
+}
 
-    #[wasm_bindgen]
-    pub fn set_animation_speed(&mut self, speed: f64) {
-        self.animation_speed = speed.max(0.1).min(5.0);
+// Interpolación del extremo de un puntero, opcionalmente siguiendo un arco
+#[derive(Clone, Debug)]
+struct PointerTween {
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    elapsed_ms: f64,
+    duration_ms: f64,
+    arc_height: f64, // desplazamiento perpendicular máximo (con signo)
+    target_block_id: Option<String>, // destino asignado al terminar
+}
+
+impl PointerTween {
+    fn current(&self) -> (f64, f64, f64) {
+        let t = (self.elapsed_ms / self.duration_ms).min(1.0);
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let (mut x, mut y, z) = (lerp(self.from.0, self.to.0), lerp(self.from.1, self.to.1), lerp(self.from.2, self.to.2));
+
+        // Desplazar perpendicularmente a la trayectoria para dibujar un arco
+        let (dx, dy) = (self.to.0 - self.from.0, self.to.1 - self.from.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length > 0.0 {
+            let offset = self.arc_height * (t * f64::consts::PI).sin();
+            x += -dy / length * offset;
+            y += dx / length * offset;
+        }
+        (x, y, z)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn reset(&mut self) {
-        self.pointers.clear();
-        self.memory_blocks.clear();
+// Altura de los arcos cruzados al intercambiar dos punteros
+const SWAP_ARC_HEIGHT: f64 = 40.0;
+
+// Distancia desde la que se desliza un nodo insertado en una lista
+const LIST_INSERT_SLIDE_OFFSET: f64 = 60.0;
+
+// Desplazamiento animado de un bloque hasta su posición final
+#[derive(Clone, Debug)]
+struct BlockSlide {
+    from: (f64, f64, f64),
+    to: (f64, f64, f64),
+    elapsed_ms: f64,
+    duration_ms: f64,
+}
+
+impl BlockSlide {
+    fn current(&self) -> (f64, f64, f64) {
+        let t = (self.elapsed_ms / self.duration_ms).min(1.0);
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        (lerp(self.from.0, self.to.0), lerp(self.from.1, self.to.1), lerp(self.from.2, self.to.2))
     }
 }
 
-// Funciones de utilidad para debugging
-#[wasm_bindgen]
-pub fn log_memory_info() {
-    console::log_1(&"Pointer Quest WASM Engine Active".into());
-    console::log_1(&"WebAssembly memory initialized successfully".into());
+// Número de decimales con los que está escrito un valor ("2.50" -> 2)
+fn decimal_places(text: &str) -> usize {
+    text.split_once('.').map(|(_, frac)| frac.len()).unwrap_or(0)
 }
 
-#[wasm_bindgen]
-pub fn benchmark_performance(iterations: usize) -> f64 {
-    let start_time = window().unwrap().performance().unwrap().now();
+// Vista de volcado hexadecimal asociada a un bloque
+#[derive(Clone, Debug)]
+struct HexDump {
+    bytes: Vec<u8>,
+    columns: usize,
+}
 
-    let mut sum = 0.0;
-    for i in 0..iterations {
-        sum += (i as f64).sin();
+impl HexDump {
+    // Filas tal y como se dibujan: etiqueta del canal de direcciones y bytes de la fila
+    fn rows(&self, base: Option<u64>) -> Vec<(String, &[u8])> {
+        self.bytes
+            .chunks(self.columns)
+            .enumerate()
+            .map(|(row, chunk)| (hex_row_label(base, row * self.columns), chunk))
+            .collect()
     }
+}
 
-    let end_time = window().unwrap().performance().unwrap().now();
-    let elapsed = end_time - start_time;
+const HEX_CELL_WIDTH: f64 = 26.0;
+const HEX_CELL_HEIGHT: f64 = 20.0;
+const HEX_GUTTER_WIDTH: f64 = 52.0;
 
-    console::log_1(&format!("Benchmark: {} iterations in {}ms", iterations, elapsed).into());
+// Etiqueta del canal de direcciones: dirección del bloque más el desplazamiento, o solo el desplazamiento
+fn hex_row_label(base: Option<u64>, offset: usize) -> String {
+    match base {
+        Some(address) => format!("{:08X}", address.wrapping_add(offset as u64)),
+        None => format!("{:04X}", offset),
+    }
+}
 
-    elapsed
+// Comparación entre dos punteros (p == q, p != q)
+#[derive(Clone, Debug)]
+struct PointerComparison {
+    id_a: String,
+    id_b: String,
+    equal: bool, // true para "==", false para "!="
 }
 
-// Inicialización de WebAssembly
-#[wasm_bindgen(start)]
-pub fn main() {
-    console::log_1(&"Pointer Quest WebAssembly module loaded successfully! 🚀".into());
+impl PointerComparison {
+    fn symbol(&self) -> &'static str {
+        if self.equal {
+            "=="
+        } else {
+            "!="
+        }
+    }
+}
+
+// Cámara 2D aplicada tras la proyección: pantalla = proyección * zoom + pan
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct Camera {
+    zoom: f64,
+    pan_x: f64,
+    pan_y: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera { zoom: 1.0, pan_x: 0.0, pan_y: 0.0 }
+    }
+}
+
+const DEFAULT_MIN_ZOOM: f64 = 0.05;
+const DEFAULT_MAX_ZOOM: f64 = 20.0;
+
+// Tamaño aproximado en memoria de las entradas de los buffers auxiliares
+const REFCOUNT_ENTRY_BYTES: usize = std::mem::size_of::<(f64, u32)>();
+
+fn null_deref_bytes(deref: &NullDeref) -> usize {
+    std::mem::size_of::<NullDeref>() + deref.pointer_id.len()
+}
+
+fn trace_entry_bytes(entry: &TraceEntry) -> usize {
+    std::mem::size_of::<TraceEntry>() + entry.from.len() + entry.to.as_ref().map_or(0, String::len) + entry.label.len()
+}
+
+fn gesture_bytes(gesture: &Gesture) -> usize {
+    std::mem::size_of::<Gesture>() + gesture.selected_id.as_ref().map_or(0, String::len)
+}
+
+// Número máximo de valores guardados en el historial de ref_count de cada bloque
+const REFCOUNT_HISTORY_LIMIT: usize = 32;
+const SPARKLINE_WIDTH: f64 = 48.0;
+
+// Eje temporal sobre el que se colocan los bloques por instante de reserva
+#[derive(Clone, Copy, Debug)]
+struct TimeAxis {
+    axis_y: f64,
+    pixels_per_ms: f64,
+    origin_ms: f64,
+}
+
+const TIME_AXIS_LEFT: f64 = 40.0;
+
+const DEFAULT_MAX_STACK_DEPTH: usize = 8;
+
+// Recorte para exportar un objeto: tamaño del PNG y traslación de sus límites
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ObjectCrop {
+    width: u32,
+    height: u32,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+// Regla de direcciones: separación entre marcas en píxeles de pantalla
+const RULER_TICK_SPACING: f64 = 100.0;
+
+#[derive(Clone, Copy, Debug)]
+struct AddressRuler {
+    base_address: u64,
+    bytes_per_pixel: f64,
+}
+
+// Puntero láser del presentador: radio del punto y longitud de la estela
+const PRESENTER_DOT_RADIUS: f64 = 6.0;
+const PRESENTER_TRAIL_LENGTH: usize = 12;
+
+// Efecto temporal (destello de un bloque o estallido de un fallo)
+#[derive(Clone, Debug)]
+struct Effect {
+    elapsed_ms: f64,
+    duration_ms: f64,
+}
+
+impl Effect {
+    fn new(duration_ms: f64) -> Self {
+        Effect { elapsed_ms: 0.0, duration_ms: duration_ms.max(1.0) }
+    }
+
+    // Intensidad restante, de 1 a 0
+    fn remaining(&self) -> f64 {
+        (1.0 - self.elapsed_ms / self.duration_ms).clamp(0.0, 1.0)
+    }
+}
+
+// Desreferencia de un puntero nulo o colgante
+#[derive(Clone, Debug, serde::Serialize)]
+struct NullDeref {
+    pointer_id: String,
+    reason: &'static str, // "null", "dangling" o "uninitialized"
+    time_ms: f64,
+}
+
+// Interacción registrada entre una variable y la memoria (desreferencia, movimiento, liberación...)
+#[derive(Clone, Debug)]
+struct TraceEntry {
+    time_ms: f64,
+    from: String,
+    to: Option<String>, // None: acción sobre el propio bloque
+    label: String,
+    failed: bool,
+}
+
+// Estado de cámara y selección capturado tras un gesto, relativo al inicio de la grabación
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct Gesture {
+    time_ms: f64,
+    camera: Camera,
+    selected_id: Option<String>,
+}
+
+// Reproducción en curso de gestos grabados
+struct GestureReplay {
+    gestures: VecDeque<Gesture>,
+    elapsed_ms: f64,
+    speed: f64,
+}
+
+// Notificación enviada al callback de eventos de JS
+#[derive(Clone, Debug, serde::Serialize)]
+struct EngineEvent {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    id: String,
+    time_ms: f64,
+}
+
+// Destino de los eventos: el callback de JS o, en las pruebas, una lista con el JSON de cada entrega
+#[derive(Clone)]
+enum EventSink {
+    Callback(js_sys::Function),
+    #[cfg(test)]
+    Recorder(std::rc::Rc<std::cell::RefCell<Vec<String>>>),
+}
+
+const MOVED_FROM_COLOR: &str = "#7F8C8D";
+
+// Grosor por unidad de peso al escalar las aristas, y sus límites
+const WEIGHT_THICKNESS_SCALE: f64 = 1.5;
+const MIN_WEIGHTED_THICKNESS: f64 = 1.0;
+const MAX_WEIGHTED_THICKNESS: f64 = 12.0;
+
+// Margen de cada escena dentro de su mitad en render_split
+const SPLIT_PADDING: f64 = 20.0;
+
+// Holgura (píxeles CSS) alrededor del encuadre para las insignias y adornos que se dibujan fuera de la huella de cada objeto
+const VIEWPORT_MARGIN: f64 = 32.0;
+
+// Longitud de la flecha de un puntero pendiente de destino
+const PENDING_ARROW_LENGTH: f64 = 30.0;
+const PENDING_MARK_FONT: &str = "bold 16px Arial";
+
+// Extremo de la flecha corta de un puntero pendiente y posición de la interrogación que la termina
+fn pending_terminus(start: (f64, f64), end: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let (ux, uy) = if length > 0.0 { (dx / length, dy / length) } else { (1.0, 0.0) };
+    let (tip_x, tip_y) = (start.0 + ux * PENDING_ARROW_LENGTH, start.1 + uy * PENDING_ARROW_LENGTH);
+    ((tip_x, tip_y), (tip_x + ux * 4.0 - 4.0, tip_y + uy * 4.0 + 6.0))
+}
+
+// Duración del destello de cada bloque durante el marcado del recolector
+const GC_MARK_PULSE_MS: f64 = 300.0;
+
+// Colores de respaldo con match_target_color para punteros sin destino válido
+const DANGLING_POINTER_COLOR: &str = "#E74C3C";
+const NULL_POINTER_COLOR: &str = "#95A5A6";
+
+// Ancho de la zona de peligro tras un array, en slots
+const DANGER_ZONE_SLOTS: f64 = 2.0;
+
+// Desplazamiento del punto de control por cada nivel de indirección
+const INDIRECTION_BEND_PER_LEVEL: f64 = 14.0;
+
+// Matriz de proyección 2x4: x' = m0·x + m1·y + m2·z + m3, y' = m4·x + m5·y + m6·z + m7
+type ProjectionMatrix = [f64; 8];
+
+const ISOMETRIC_PROJECTION: ProjectionMatrix = [1.0, 0.0, -0.5, 0.0, 0.0, 1.0, -0.5, 0.0];
+const ORTHOGRAPHIC_PROJECTION: ProjectionMatrix = [1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+const CABINET_PROJECTION: ProjectionMatrix = [1.0, 0.0, 0.35, 0.0, 0.0, 1.0, -0.35, 0.0];
+
+fn apply_projection(m: &ProjectionMatrix, x: f64, y: f64, z: f64) -> (f64, f64) {
+    (m[0] * x + m[1] * y + m[2] * z + m[3], m[4] * x + m[5] * y + m[6] * z + m[7])
+}
+
+// 8 coeficientes (2x4) o 6 (2x3, sin traslación), todos finitos
+fn projection_from_coefficients(coefficients: &[f64]) -> Result<ProjectionMatrix, EngineError> {
+    let matrix: ProjectionMatrix = match coefficients {
+        [a, b, c, d, e, f, g, h] => [*a, *b, *c, *d, *e, *f, *g, *h],
+        [a, b, c, e, f, g] => [*a, *b, *c, 0.0, *e, *f, *g, 0.0],
+        _ => {
+            return Err(EngineError(format!(
+                "Projection matrix needs 6 or 8 coefficients, got {}",
+                coefficients.len()
+            )))
+        }
+    };
+    if matrix.iter().any(|c| !c.is_finite()) {
+        return Err(EngineError("Projection matrix coefficients must be finite".to_string()));
+    }
+    Ok(matrix)
+}
+
+// Vista adicional de la escena con su propia cámara y, opcionalmente, su propia proyección
+#[derive(Clone, Debug, serde::Deserialize)]
+struct View {
+    #[serde(flatten)]
+    camera: Camera,
+    #[serde(default)]
+    projection: Option<Vec<f64>>,
+}
+
+// Opacidad de los objetos que quedan fuera del resaltado activo
+const DIMMED_ALPHA: f64 = 0.25;
+
+// Escena completa serializable (usada para exportar/importar)
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Scene {
+    pointers: Vec<Pointer3D>,
+    memory_blocks: Vec<MemoryBlock3D>,
+}
+
+// Modelo mínimo de renderizado (solo geometría proyectada, colores y texto)
+#[derive(serde::Serialize)]
+struct RenderModel {
+    blocks: Vec<RenderBlock>,
+    pointers: Vec<RenderPointer>,
+}
+
+#[derive(serde::Serialize)]
+struct RenderBlock {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    color: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct RenderPointer {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+    color: String,
+    thickness: f64,
+}
+
+// Motor de animaciones principal
+#[wasm_bindgen]
+pub struct AnimationEngine {
+    pointers: HashMap<String, Pointer3D>,
+    memory_blocks: HashMap<String, MemoryBlock3D>,
+    value_tweens: HashMap<String, ValueTween>,
+    color_tweens: HashMap<String, ColorTween>,
+    color_transition_ms: f64,
+    pointer_tweens: HashMap<String, PointerTween>,
+    block_slides: HashMap<String, BlockSlide>,
+    stride_indicators: HashMap<String, i64>,
+    block_flashes: HashMap<String, Effect>,
+    crash_bursts: Vec<((f64, f64), Effect)>,
+    null_derefs: VecDeque<NullDeref>,
+    trace: VecDeque<TraceEntry>,
+    hex_dumps: HashMap<String, HexDump>,
+    // Ids resaltados; si hay resaltado activo, el resto se atenúa
+    highlighted: Option<HashSet<String>>,
+    selected_id: Option<String>,
+    show_focus_order: bool,
+    image_smoothing: bool,
+    render_3d_boxes: bool,
+    type_flow: bool,
+    indirection_bend: bool,
+    show_storage_classes: bool,
+    scale_thickness_by_weight: bool,
+    show_self_references: bool,
+    target_inference_radius: Option<f64>,
+    cross_region_color: Option<String>,
+    match_target_color: bool,
+    disabled_diagnostics: HashSet<String>,
+    comparisons: Vec<PointerComparison>,
+    pointer_groups: HashMap<String, Vec<String>>,
+    camera: Camera,
+    projection: ProjectionMatrix,
+    views: BTreeMap<String, (Camera, Option<ProjectionMatrix>)>,
+    next_view_id: u32,
+    min_zoom: f64,
+    max_zoom: f64,
+    device_pixel_ratio: f64,
+    hairline: bool,
+    refcount_history: HashMap<String, VecDeque<(f64, u32)>>, // (instante, ref_count)
+    show_refcount_history: bool,
+    node_depths: BTreeMap<String, usize>,
+    array_bounds: HashMap<String, usize>,
+    gc_marked: Option<HashSet<String>>,
+    gc_mark_schedule: VecDeque<(f64, String)>, // (retardo restante en ms, bloque)
+    block_fades: HashMap<String, Effect>, // bloques que se desvanecen antes de eliminarse
+    pointer_fades: HashMap<String, Effect>,
+    block_growths: HashMap<String, Effect>, // bloques recién añadidos que crecen desde su centro
+    show_node_depths: bool,
+    time_axis: Option<TimeAxis>,
+    // Redibujado automático tras cada mutación (agrupable con begin_batch/end_batch)
+    render_target: Option<String>,
+    dirty: bool,
+    batch_depth: u32,
+    render_count: u32,
+    speed_ramp: Option<SpeedRamp>,
+    speed_ramp_easing: Easing,
+    cursor_readout: Option<(f64, f64)>,
+    show_depth_meter: bool,
+    max_stack_depth: usize,
+    address_ruler: Option<AddressRuler>,
+    presenter_pointer: Option<(f64, f64)>,
+    presenter_trail: VecDeque<(f64, f64)>,
+    title: Option<(String, TitlePosition)>,
+    gesture_recording: Option<(f64, Vec<Gesture>)>,
+    memory_budget: Option<usize>,
+    gesture_replay: Option<GestureReplay>,
+    event_callback: Option<EventSink>,
+    event_coalescing_ms: f64,
+    pending_events: BTreeMap<&'static str, Vec<EngineEvent>>,
+    animation_speed: f64,
+    last_frame_time: f64,
+    // Reloj manual (ms) para lecciones y pruebas deterministas
+    manual_time: Option<f64>,
+}
+
+#[wasm_bindgen]
+impl AnimationEngine {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> AnimationEngine {
+        console::log_1(&"AnimationEngine initialized".into());
+        Self::empty()
+    }
+
+    // Motor sin objetos con la configuración por defecto
+    fn empty() -> AnimationEngine {
+        Self::with_clock(None)
+    }
+
+    // Igual que empty() pero con el reloj manual ya fijado si se indica; sin él no hace falta `window`
+    fn with_clock(manual_time: Option<f64>) -> AnimationEngine {
+        AnimationEngine {
+            pointers: HashMap::new(),
+            memory_blocks: HashMap::new(),
+            value_tweens: HashMap::new(),
+            color_tweens: HashMap::new(),
+            color_transition_ms: 0.0,
+            pointer_tweens: HashMap::new(),
+            block_slides: HashMap::new(),
+            stride_indicators: HashMap::new(),
+            block_flashes: HashMap::new(),
+            crash_bursts: Vec::new(),
+            null_derefs: VecDeque::new(),
+            trace: VecDeque::new(),
+            hex_dumps: HashMap::new(),
+            highlighted: None,
+            selected_id: None,
+            show_focus_order: false,
+            image_smoothing: true,
+            render_3d_boxes: false,
+            type_flow: false,
+            indirection_bend: false,
+            show_storage_classes: false,
+            scale_thickness_by_weight: false,
+            show_self_references: false,
+            target_inference_radius: None,
+            cross_region_color: None,
+            match_target_color: false,
+            disabled_diagnostics: HashSet::new(),
+            comparisons: Vec::new(),
+            pointer_groups: HashMap::new(),
+            camera: Camera::default(),
+            projection: ISOMETRIC_PROJECTION,
+            views: BTreeMap::new(),
+            next_view_id: 0,
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            device_pixel_ratio: 1.0,
+            hairline: false,
+            refcount_history: HashMap::new(),
+            show_refcount_history: false,
+            node_depths: BTreeMap::new(),
+            array_bounds: HashMap::new(),
+            gc_marked: None,
+            gc_mark_schedule: VecDeque::new(),
+            block_fades: HashMap::new(),
+            pointer_fades: HashMap::new(),
+            block_growths: HashMap::new(),
+            show_node_depths: false,
+            animation_speed: 1.0,
+            time_axis: None,
+            render_target: None,
+            dirty: false,
+            batch_depth: 0,
+            render_count: 0,
+            speed_ramp: None,
+            speed_ramp_easing: Easing::EaseInOut,
+            cursor_readout: None,
+            show_depth_meter: false,
+            max_stack_depth: DEFAULT_MAX_STACK_DEPTH,
+            address_ruler: None,
+            presenter_pointer: None,
+            title: None,
+            presenter_trail: VecDeque::new(),
+            gesture_recording: None,
+            memory_budget: None,
+            gesture_replay: None,
+            event_callback: None,
+            event_coalescing_ms: 0.0,
+            pending_events: BTreeMap::new(),
+            last_frame_time: manual_time.unwrap_or_else(performance_now),
+            manual_time,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn add_pointer(&mut self, pointer: JsValue) {
+        let ptr: Pointer3D = serde_wasm_bindgen::from_value(pointer).unwrap();
+        self.insert_pointer(ptr);
+        self.sync_pointer_sources(None);
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn add_memory_block(&mut self, block: JsValue) {
+        self.add_block(serde_wasm_bindgen::from_value(block).unwrap());
+    }
+
+    fn add_block(&mut self, mut mem_block: MemoryBlock3D) {
+        mem_block.allocated_at.get_or_insert(self.now());
+        let id = mem_block.id.clone();
+        self.insert_block(mem_block);
+        self.sync_pointer_sources(Some(&id));
+        self.mark_dirty();
+    }
+
+    // Inserta (o reemplaza) un objeto; el alta se notifica ya con el objeto en la escena
+    fn insert_pointer(&mut self, pointer: Pointer3D) {
+        let id = pointer.id.clone();
+        if self.pointers.insert(id.clone(), pointer).is_none() {
+            self.emit_event("pointer_added", &id);
+        }
+    }
+
+    fn insert_block(&mut self, block: MemoryBlock3D) {
+        let id = block.id.clone();
+        if self.memory_blocks.insert(id.clone(), block).is_none() {
+            self.emit_event("block_added", &id);
+        }
+    }
+
+    // Devuelve error si el puntero está bloqueado (antes no devolvía nada)
+    #[wasm_bindgen]
+    pub fn remove_pointer(&mut self, id: &str) -> Result<(), JsValue> {
+        self.ensure_unlocked(id)?;
+        self.detach_pointer(id);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    fn detach_pointer(&mut self, id: &str) {
+        let removed = self.pointers.remove(id).is_some();
+        self.pointer_tweens.remove(id);
+        self.pointer_fades.remove(id);
+        self.stride_indicators.remove(id);
+        self.color_tweens.remove(id);
+        self.clear_selection_of(id);
+        self.comparisons.retain(|c| c.id_a != id && c.id_b != id);
+        for members in self.pointer_groups.values_mut() {
+            members.retain(|member| member != id);
+        }
+        if removed {
+            self.emit_event("pointer_removed", id);
+        }
+    }
+
+    // Devuelve error si el bloque está bloqueado (antes no devolvía nada)
+    #[wasm_bindgen]
+    pub fn remove_memory_block(&mut self, id: &str) -> Result<(), JsValue> {
+        Ok(self.remove_block(id)?)
+    }
+
+    fn remove_block(&mut self, id: &str) -> Result<(), EngineError> {
+        self.ensure_unlocked(id)?;
+        self.discard_block(id);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    fn discard_block(&mut self, id: &str) {
+        let removed = self.memory_blocks.remove(id).is_some();
+        self.value_tweens.remove(id);
+        self.color_tweens.remove(id);
+        self.block_slides.remove(id);
+        self.block_flashes.remove(id);
+        self.clear_selection_of(id);
+        self.hex_dumps.remove(id);
+        self.refcount_history.remove(id);
+        self.node_depths.remove(id);
+        self.array_bounds.remove(id);
+        self.block_fades.remove(id);
+        self.block_growths.remove(id);
+        if let Some(marked) = self.gc_marked.as_mut() {
+            marked.remove(id);
+        }
+        if removed {
+            self.emit_event("block_removed", id);
+        }
+    }
+
+    // Devuelve error si el puntero está bloqueado (antes no devolvía nada)
+    #[wasm_bindgen]
+    pub fn update_pointer_position(&mut self, id: &str, end_x: f64, end_y: f64, end_z: f64) -> Result<(), JsValue> {
+        self.ensure_unlocked(id)?;
+        if let Some(pointer) = self.pointers.get_mut(id) {
+            pointer.end_x = end_x;
+            pointer.end_y = end_y;
+            pointer.end_z = end_z;
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn move_memory_block(&mut self, id: &str, x: f64, y: f64, z: f64) -> Result<(), JsValue> {
+        self.ensure_unlocked(id)?;
+        if let Some(block) = self.memory_blocks.get_mut(id) {
+            block.x = x;
+            block.y = y;
+            block.z = z;
+        }
+        self.sync_pointer_sources(Some(id));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Los objetos bloqueados no se pueden mover ni eliminar
+    #[wasm_bindgen]
+    pub fn set_locked(&mut self, id: &str, locked: bool) -> Result<(), JsValue> {
+        if let Some(pointer) = self.pointers.get_mut(id) {
+            pointer.locked = locked;
+        } else if let Some(block) = self.memory_blocks.get_mut(id) {
+            block.locked = locked;
+        } else {
+            return Err(JsValue::from_str(&format!("Object not found: {}", id)));
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    fn is_locked(&self, id: &str) -> bool {
+        self.pointers.get(id).is_some_and(|p| p.locked) || self.memory_blocks.get(id).is_some_and(|b| b.locked)
+    }
+
+    fn ensure_unlocked(&self, id: &str) -> Result<(), EngineError> {
+        if self.is_locked(id) {
+            return Err(EngineError(format!("Object is locked: {}", id)));
+        }
+        Ok(())
+    }
+
+    fn ensure_rebindable(&self, id: &str) -> Result<(), EngineError> {
+        if self.pointers.get(id).is_some_and(|pointer| !pointer.kind.rebindable()) {
+            return Err(EngineError(format!("References cannot be rebound: {}", id)));
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn animate(&mut self, _delta_time: f64) {
+        let current_time = self.now();
+        let actual_delta = (current_time - self.last_frame_time) / 1000.0;
+        self.last_frame_time = current_time;
+        self.advance_speed_ramp(actual_delta * 1000.0);
+        self.advance_gesture_replay(actual_delta * 1000.0);
+        self.flush_due_events(current_time);
+
+        // Animar punteros
+        for pointer in self.pointers.values_mut() {
+            if pointer.animated {
+                pointer.animation_progress += actual_delta * self.animation_speed;
+
+                if pointer.animation_progress > 1.0 {
+                    pointer.animation_progress = 0.0;
+                }
+
+                // Efecto de pulso para punteros animados
+                let pulse = (pointer.animation_progress * f64::consts::PI * 2.0).sin();
+                pointer.thickness = 3.0 + pulse * 2.0;
+            }
+        }
+
+        // Comprobar antes de avanzar para que también se dibuje el fotograma final
+        let animating = self.is_animating();
+        let delta_ms = actual_delta * 1000.0 * self.animation_speed;
+        self.advance_value_tweens(delta_ms);
+        self.advance_color_tweens(delta_ms);
+        self.advance_block_slides(delta_ms);
+        self.advance_pointer_tweens(delta_ms);
+        self.advance_effects(delta_ms);
+        if animating {
+            self.mark_dirty();
+        }
+    }
+
+    // Hay algo en movimiento que obliga a redibujar en cada fotograma
+    fn is_animating(&self) -> bool {
+        self.pointers.values().any(|pointer| pointer.animated)
+            || !self.value_tweens.is_empty()
+            || !self.color_tweens.is_empty()
+            || !self.block_slides.is_empty()
+            || !self.pointer_tweens.is_empty()
+            || !self.block_flashes.is_empty()
+            || !self.crash_bursts.is_empty()
+            || !self.gc_mark_schedule.is_empty()
+            || !self.block_fades.is_empty()
+            || !self.pointer_fades.is_empty()
+            || !self.block_growths.is_empty()
+    }
+
+    // Desreferencia animada: destella el destino o, si el puntero es nulo/colgante, registra el fallo
+    #[wasm_bindgen]
+    pub fn animate_dereference(&mut self, pointer_id: &str, duration_ms: f64) -> Result<bool, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+
+        let reason = if pointer.pending {
+            Some("uninitialized")
+        } else if self.is_pointer_null(pointer) {
+            Some("null")
+        } else if self.is_pointer_dangling(pointer) {
+            Some("dangling")
+        } else {
+            None
+        };
+
+        let position = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+        let target = self.target_of(pointer).map(str::to_string);
+        let source = self.trace_participant(pointer_id);
+        if let Some(reason) = reason {
+            let target = target.unwrap_or_else(|| "null".to_string());
+            self.record_trace(&source, Some(&target), format!("*{} ({})", pointer_id, reason), true);
+            self.null_derefs.push_back(NullDeref {
+                pointer_id: pointer_id.to_string(),
+                reason,
+                time_ms: self.now(),
+            });
+            self.enforce_memory_budget();
+            self.crash_bursts.push((position, Effect::new(duration_ms)));
+            self.emit_event("null_deref", pointer_id);
+            self.mark_dirty();
+            return Ok(false);
+        }
+
+        if let Some(target) = target {
+            self.record_trace(&source, Some(&target), format!("*{}", pointer_id), false);
+            self.block_flashes.insert(target, Effect::new(duration_ms));
+        }
+        self.mark_dirty();
+        Ok(true)
+    }
+
+    // Traza de desreferencias y mutaciones como diagrama de secuencia de Mermaid
+    #[wasm_bindgen]
+    pub fn export_sequence_diagram(&self) -> String {
+        let mut participants: Vec<&str> = Vec::new();
+        for entry in &self.trace {
+            for name in std::iter::once(entry.from.as_str()).chain(entry.to.as_deref()) {
+                if !participants.contains(&name) {
+                    participants.push(name);
+                }
+            }
+        }
+
+        let mut lines = vec!["sequenceDiagram".to_string()];
+        lines.extend(participants.iter().map(|name| format!("    participant {}", name)));
+        for entry in &self.trace {
+            lines.push(match entry.to {
+                Some(ref to) => {
+                    let arrow = if entry.failed { "-x" } else { "->>" };
+                    format!("    {}{}{}: {}", entry.from, arrow, to, entry.label)
+                }
+                None => format!("    Note over {}: {}", entry.from, entry.label),
+            });
+        }
+        lines.join("\n")
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    // En el diagrama, un puntero habla desde la variable (bloque) que lo guarda
+    fn trace_participant(&self, pointer_id: &str) -> String {
+        self.pointers
+            .get(pointer_id)
+            .and_then(|pointer| self.storage_block_of(pointer))
+            .map(|block| block.id.clone())
+            .unwrap_or_else(|| pointer_id.to_string())
+    }
+
+    fn record_trace(&mut self, from: &str, to: Option<&str>, label: String, failed: bool) {
+        self.trace.push_back(TraceEntry {
+            time_ms: self.now(),
+            from: from.to_string(),
+            to: to.map(str::to_string),
+            label,
+            failed,
+        });
+        self.enforce_memory_budget();
+    }
+
+    // Fallos por desreferencias nulas o colgantes registrados hasta ahora
+    #[wasm_bindgen]
+    pub fn get_null_derefs(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.null_derefs)
+    }
+
+    fn is_pointer_null(&self, pointer: &Pointer3D) -> bool {
+        pointer.is_null || (pointer.target_block_id.is_none() && pointer.target_address == Some(0))
+    }
+
+    fn advance_effects(&mut self, delta_ms: f64) {
+        for effect in self.block_flashes.values_mut() {
+            effect.elapsed_ms += delta_ms;
+        }
+        self.block_flashes.retain(|_, effect| effect.remaining() > 0.0);
+
+        // Fase de marcado: cada bloque destella cuando le llega su turno
+        for (delay, _) in self.gc_mark_schedule.iter_mut() {
+            *delay -= delta_ms;
+        }
+        while self.gc_mark_schedule.front().is_some_and(|(delay, _)| *delay <= 0.0) {
+            let (_, id) = self.gc_mark_schedule.pop_front().unwrap();
+            self.block_flashes.insert(id, Effect::new(GC_MARK_PULSE_MS));
+        }
+
+        // Fase de barrido: los bloques se desvanecen y después desaparecen
+        for effect in self.block_fades.values_mut() {
+            effect.elapsed_ms += delta_ms;
+        }
+        let swept: Vec<String> = self
+            .block_fades
+            .iter()
+            .filter(|(_, effect)| effect.remaining() <= 0.0)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in swept {
+            self.discard_block(&id);
+            self.mark_dirty();
+        }
+
+        for effect in self.pointer_fades.values_mut().chain(self.block_growths.values_mut()) {
+            effect.elapsed_ms += delta_ms;
+        }
+        self.block_growths.retain(|_, effect| effect.remaining() > 0.0);
+        let faded: Vec<String> = self
+            .pointer_fades
+            .iter()
+            .filter(|(_, effect)| effect.remaining() <= 0.0)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in faded {
+            self.detach_pointer(&id);
+            self.mark_dirty();
+        }
+
+        for (_, effect) in self.crash_bursts.iter_mut() {
+            effect.elapsed_ms += delta_ms;
+        }
+        self.crash_bursts.retain(|(_, effect)| effect.remaining() > 0.0);
+    }
+
+    // Marcado del recolector: destella uno a uno los bloques alcanzables desde las raíces (bloques o punteros)
+    #[wasm_bindgen]
+    pub fn animate_gc_mark(&mut self, root_ids: JsValue, duration_ms: f64) -> Result<JsValue, JsValue> {
+        let roots: Vec<String> = serde_wasm_bindgen::from_value(root_ids)?;
+        to_js(&self.gc_mark(&roots, duration_ms)?)
+    }
+
+    fn gc_mark(&mut self, roots: &[String], duration_ms: f64) -> Result<Vec<String>, EngineError> {
+        if let Some(missing) = roots.iter().find(|id| self.object_rect(id).is_none()) {
+            return Err(EngineError(format!("Object not found: {}", missing)));
+        }
+
+        let marked: Vec<String> = self
+            .reachable_order(roots)
+            .into_iter()
+            .filter(|id| self.memory_blocks.contains_key(id))
+            .collect();
+        let step_ms = duration_ms.max(0.0) / marked.len().max(1) as f64;
+        self.gc_mark_schedule = marked.iter().enumerate().map(|(i, id)| (i as f64 * step_ms, id.clone())).collect();
+        self.gc_marked = Some(marked.iter().cloned().collect());
+        self.advance_effects(0.0);
+        self.mark_dirty();
+
+        let mut ids = marked;
+        ids.sort();
+        Ok(ids)
+    }
+
+    // Barrido: desvanece y elimina los bloques que no se marcaron; devuelve sus ids
+    #[wasm_bindgen]
+    pub fn animate_gc_sweep(&mut self, duration_ms: f64) -> Result<JsValue, JsValue> {
+        to_js(&self.gc_sweep(duration_ms)?)
+    }
+
+    fn gc_sweep(&mut self, duration_ms: f64) -> Result<Vec<String>, EngineError> {
+        let marked = self
+            .gc_marked
+            .take()
+            .ok_or_else(|| EngineError("No mark phase to sweep; call animate_gc_mark first".to_string()))?;
+        self.gc_mark_schedule.clear();
+
+        // Los bloques bloqueados sobreviven al barrido aunque nadie los alcance
+        let mut garbage: Vec<String> = self
+            .memory_blocks
+            .keys()
+            .filter(|id| !marked.contains(*id) && !self.is_locked(id))
+            .cloned()
+            .collect();
+        garbage.sort();
+        for id in &garbage {
+            if duration_ms > 0.0 {
+                self.block_fades.insert(id.clone(), Effect::new(duration_ms));
+            } else {
+                self.discard_block(id);
+            }
+        }
+        self.mark_dirty();
+        Ok(garbage)
+    }
+
+    // Transición a otra escena: lo nuevo crece, lo que sobra se desvanece, lo que cambia de sitio se
+    // desplaza y los valores modificados destellan, todo a la vez durante duration_ms
+    #[wasm_bindgen]
+    pub fn animate_morph_to(&mut self, target_json: &str, duration_ms: f64) -> Result<(), JsValue> {
+        let scene: Scene = serde_json::from_str(target_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid scene JSON: {}", e)))?;
+        ensure_finite(scene.pointers.iter(), scene.memory_blocks.iter())?;
+        let animated = duration_ms > 0.0;
+
+        // Lo que no está en la escena destino se desvanece (o desaparece sin animación); lo bloqueado
+        // se queda tal cual
+        let block_ids: HashSet<&str> = scene.memory_blocks.iter().map(|b| b.id.as_str()).collect();
+        let pointer_ids: HashSet<&str> = scene.pointers.iter().map(|p| p.id.as_str()).collect();
+        let removed_blocks: Vec<String> = self
+            .memory_blocks
+            .keys()
+            .filter(|id| !block_ids.contains(id.as_str()) && !self.is_locked(id))
+            .cloned()
+            .collect();
+        let removed_pointers: Vec<String> = self
+            .pointers
+            .keys()
+            .filter(|id| !pointer_ids.contains(id.as_str()) && !self.is_locked(id))
+            .cloned()
+            .collect();
+        for id in removed_blocks {
+            if animated {
+                self.block_fades.insert(id, Effect::new(duration_ms));
+            } else {
+                self.discard_block(&id);
+            }
+        }
+        for id in removed_pointers {
+            if animated {
+                self.pointer_fades.insert(id, Effect::new(duration_ms));
+            } else {
+                self.detach_pointer(&id);
+            }
+        }
+
+        for mut block in scene.memory_blocks {
+            let id = block.id.clone();
+            if self.is_locked(&id) {
+                continue;
+            }
+            self.block_fades.remove(&id);
+            // Los efectos que queden de animaciones anteriores no deben alejar el bloque de su estado destino
+            match self.memory_blocks.get(&id) {
+                Some(current) => {
+                    let from = (current.x, current.y, current.z);
+                    let to = (block.x, block.y, block.z);
+                    if animated && current.value != block.value {
+                        self.block_flashes.insert(id.clone(), Effect::new(duration_ms));
+                    } else {
+                        self.block_flashes.remove(&id);
+                    }
+                    if animated && from != to {
+                        (block.x, block.y, block.z) = from;
+                        self.block_slides.insert(id.clone(), BlockSlide { from, to, elapsed_ms: 0.0, duration_ms });
+                    } else {
+                        self.block_slides.remove(&id);
+                    }
+                    self.block_growths.remove(&id);
+                }
+                None if animated => {
+                    self.block_growths.insert(id.clone(), Effect::new(duration_ms));
+                }
+                None => {
+                    self.block_growths.remove(&id);
+                }
+            }
+            self.insert_block(block);
+        }
+
+        // Los punteros nuevos salen de su origen; los existentes llevan su extremo al de la escena destino,
+        // salvo los bloqueados y los fijados, que conservan el suyo
+        for mut pointer in scene.pointers {
+            let id = pointer.id.clone();
+            if self.is_locked(&id) || self.pointers.get(&id).is_some_and(|current| current.pinned) {
+                continue;
+            }
+            self.pointer_fades.remove(&id);
+            let (from, current_target) = match self.pointers.get(&id) {
+                Some(current) => ((current.end_x, current.end_y, current.end_z), current.target_block_id.clone()),
+                None => ((pointer.start_x, pointer.start_y, pointer.start_z), None),
+            };
+            let to = (pointer.end_x, pointer.end_y, pointer.end_z);
+            if animated && from != to {
+                let tween = PointerTween {
+                    from,
+                    to,
+                    elapsed_ms: 0.0,
+                    duration_ms,
+                    arc_height: 0.0,
+                    target_block_id: pointer.target_block_id.clone(),
+                };
+                pointer.target_block_id = current_target;
+                (pointer.end_x, pointer.end_y, pointer.end_z) = from;
+                self.pointer_tweens.insert(id.clone(), tween);
+            } else {
+                self.pointer_tweens.remove(&id);
+            }
+            self.insert_pointer(pointer);
+        }
+
+        self.sync_pointer_sources(None);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Intercambia los destinos de dos punteros con arcos que se cruzan
+    #[wasm_bindgen]
+    pub fn animate_swap(&mut self, pointer_a: &str, pointer_b: &str, duration_ms: f64) -> Result<(), JsValue> {
+        let a = self
+            .pointers
+            .get(pointer_a)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_a)))?;
+        let b = self
+            .pointers
+            .get(pointer_b)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_b)))?;
+        self.ensure_unlocked(pointer_a)?;
+        self.ensure_unlocked(pointer_b)?;
+        self.ensure_rebindable(pointer_a)?;
+        self.ensure_rebindable(pointer_b)?;
+
+        let end_a = (a.end_x, a.end_y, a.end_z);
+        let end_b = (b.end_x, b.end_y, b.end_z);
+        let tween_a = PointerTween {
+            from: end_a,
+            to: end_b,
+            elapsed_ms: 0.0,
+            duration_ms: duration_ms.max(0.0),
+            arc_height: SWAP_ARC_HEIGHT,
+            target_block_id: b.target_block_id.clone(),
+        };
+        let tween_b = PointerTween {
+            from: end_b,
+            to: end_a,
+            elapsed_ms: 0.0,
+            duration_ms: duration_ms.max(0.0),
+            arc_height: SWAP_ARC_HEIGHT,
+            target_block_id: a.target_block_id.clone(),
+        };
+
+        // Con uno solo fijado, el otro acabaría apuntando al mismo bloque: no se intercambia ninguno
+        if a.pinned || b.pinned {
+            return Ok(());
+        }
+        self.pointer_tweens.insert(pointer_a.to_string(), tween_a);
+        self.pointer_tweens.insert(pointer_b.to_string(), tween_b);
+        if duration_ms <= 0.0 {
+            self.advance_pointer_tweens(0.0);
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Lleva el extremo del puntero hasta el ancla de un bloque y lo apunta a él al terminar
+    #[wasm_bindgen]
+    pub fn animate_pointer_to(&mut self, pointer_id: &str, block_id: &str, duration_ms: f64) -> Result<(), JsValue> {
+        Ok(self.tween_pointer_to(pointer_id, block_id, duration_ms)?)
+    }
+
+    fn tween_pointer_to(&mut self, pointer_id: &str, block_id: &str, duration_ms: f64) -> Result<(), EngineError> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| EngineError(format!("Pointer not found: {}", pointer_id)))?;
+        let to = self
+            .memory_blocks
+            .get(block_id)
+            .map(block_anchor)
+            .ok_or_else(|| EngineError(format!("Memory block not found: {}", block_id)))?;
+        self.ensure_unlocked(pointer_id)?;
+        self.ensure_rebindable(pointer_id)?;
+        if pointer.pinned {
+            return Ok(());
+        }
+
+        let tween = PointerTween {
+            from: (pointer.end_x, pointer.end_y, pointer.end_z),
+            to,
+            elapsed_ms: 0.0,
+            duration_ms: duration_ms.max(0.0),
+            arc_height: 0.0,
+            target_block_id: Some(block_id.to_string()),
+        };
+        self.pointer_tweens.insert(pointer_id.to_string(), tween);
+        if duration_ms <= 0.0 {
+            self.advance_pointer_tweens(0.0);
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Inserta un nodo entre `prev_id` y `next_id`: el nodo entra deslizándose, prev.next pasa a apuntarle
+    // y su propio puntero next (creado si no existe) apunta a `next_id`
+    #[wasm_bindgen]
+    pub fn animate_list_insert(
+        &mut self,
+        prev_id: &str,
+        new_block: JsValue,
+        next_id: &str,
+        duration_ms: f64,
+    ) -> Result<(), JsValue> {
+        let block: MemoryBlock3D = serde_wasm_bindgen::from_value(new_block)?;
+        Ok(self.list_insert(prev_id, block, next_id, duration_ms)?)
+    }
+
+    fn list_insert(&mut self, prev_id: &str, block: MemoryBlock3D, next_id: &str, duration_ms: f64) -> Result<(), EngineError> {
+        for id in [prev_id, next_id] {
+            if !self.memory_blocks.contains_key(id) {
+                return Err(EngineError(format!("Memory block not found: {}", id)));
+            }
+        }
+        if self.memory_blocks.contains_key(&block.id) {
+            return Err(EngineError(format!("Memory block already exists: {}", block.id)));
+        }
+        let prev_next = self
+            .next_pointer_of(prev_id, next_id)
+            .ok_or_else(|| EngineError(format!("No pointer stored in block: {}", prev_id)))?;
+        // El nodo nuevo aún no está en la escena: su puntero next, si ya lo tiene, es el
+        // que lo declara como origen
+        let existing_next = self.preferred_pointer(
+            self.pointers.values().filter(|pointer| pointer.source_block_id.as_deref() == Some(block.id.as_str())),
+            next_id,
+        );
+        for id in std::iter::once(&prev_next).chain(existing_next.as_ref()) {
+            self.ensure_unlocked(id)?;
+            self.ensure_rebindable(id)?;
+        }
+        // Como en el resto de reasignaciones, un puntero fijado deja la operación sin efecto
+        if std::iter::once(&prev_next).chain(existing_next.as_ref()).any(|id| self.is_pinned(id)) {
+            return Ok(());
+        }
+
+        let new_id = block.id.clone();
+        let to = (block.x, block.y, block.z);
+        self.add_block(block.clone());
+
+        // El puntero next del nodo nuevo: el que ya guarde o uno con el estilo de prev.next
+        let new_next = match existing_next {
+            Some(id) => id,
+            None => {
+                let template = &self.pointers[&prev_next];
+                let (x, y, z) = block_anchor(&block);
+                let pointer = Pointer3D {
+                    id: format!("{}.next", new_id),
+                    start_x: x,
+                    start_y: y,
+                    start_z: z,
+                    end_x: x,
+                    end_y: y,
+                    end_z: z,
+                    color: template.color.clone(),
+                    thickness: template.thickness,
+                    kind: template.kind,
+                    type_name: template.type_name.clone(),
+                    target_type_name: template.target_type_name.clone(),
+                    source_block_id: Some(new_id.clone()),
+                    ..Pointer3D::default()
+                };
+                let id = pointer.id.clone();
+                self.insert_pointer(pointer);
+                id
+            }
+        };
+
+        if duration_ms > 0.0 {
+            let from = (to.0, to.1 + LIST_INSERT_SLIDE_OFFSET, to.2);
+            self.block_slides.insert(new_id.clone(), BlockSlide { from, to, elapsed_ms: 0.0, duration_ms });
+            self.advance_block_slides(0.0);
+        }
+        self.tween_pointer_to(&prev_next, &new_id, duration_ms)?;
+        self.tween_pointer_to(&new_next, next_id, duration_ms)?;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Puntero guardado en `block_id`, con preferencia por el que apunta a `target_id`
+    fn next_pointer_of(&self, block_id: &str, target_id: &str) -> Option<String> {
+        self.preferred_pointer(
+            self.pointers
+                .values()
+                .filter(|pointer| self.storage_block_of(pointer).is_some_and(|block| block.id == block_id)),
+            target_id,
+        )
+    }
+
+    // El primero por id de los que apuntan a `target_id` o, si ninguno, el primero por id
+    fn preferred_pointer<'a>(&self, candidates: impl Iterator<Item = &'a Pointer3D>, target_id: &str) -> Option<String> {
+        candidates
+            .min_by_key(|pointer| (self.target_of(pointer) != Some(target_id), pointer.id.as_str()))
+            .map(|pointer| pointer.id.clone())
+    }
+
+    fn advance_block_slides(&mut self, delta_ms: f64) {
+        let mut finished = Vec::new();
+
+        for (id, slide) in self.block_slides.iter_mut() {
+            slide.elapsed_ms += delta_ms;
+            let done = slide.elapsed_ms >= slide.duration_ms;
+
+            if let Some(block) = self.memory_blocks.get_mut(id) {
+                let (x, y, z) = if done { slide.to } else { slide.current() };
+                block.x = x;
+                block.y = y;
+                block.z = z;
+            }
+            if done {
+                finished.push(id.clone());
+            }
+        }
+
+        let moving: Vec<String> = self.block_slides.keys().cloned().collect();
+        for id in moving {
+            self.sync_pointer_sources(Some(&id));
+        }
+        for id in finished {
+            self.block_slides.remove(&id);
+        }
+    }
+
+    // Un puntero fijado conserva su extremo y destino: ignora reasignaciones y animaciones
+    #[wasm_bindgen]
+    pub fn pin_pointer(&mut self, pointer_id: &str) -> Result<(), JsValue> {
+        self.set_pinned(pointer_id, true)
+    }
+
+    #[wasm_bindgen]
+    pub fn unpin_pointer(&mut self, pointer_id: &str) -> Result<(), JsValue> {
+        self.set_pinned(pointer_id, false)
+    }
+
+    fn set_pinned(&mut self, pointer_id: &str, pinned: bool) -> Result<(), JsValue> {
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        pointer.pinned = pinned;
+        if pinned {
+            self.pointer_tweens.remove(pointer_id);
+            self.stride_indicators.remove(pointer_id);
+        }
+        Ok(())
+    }
+
+    fn is_pinned(&self, pointer_id: &str) -> bool {
+        self.pointers.get(pointer_id).is_some_and(|pointer| pointer.pinned)
+    }
+
+    fn advance_pointer_tweens(&mut self, delta_ms: f64) {
+        let mut finished = Vec::new();
+
+        for (id, tween) in self.pointer_tweens.iter_mut() {
+            tween.elapsed_ms += delta_ms;
+            let done = tween.elapsed_ms >= tween.duration_ms;
+
+            if let Some(pointer) = self.pointers.get_mut(id) {
+                let (x, y, z) = if done { tween.to } else { tween.current() };
+                pointer.end_x = x;
+                pointer.end_y = y;
+                pointer.end_z = z;
+                if done {
+                    pointer.target_block_id = tween.target_block_id.clone();
+                }
+            }
+            if done {
+                finished.push(id.clone());
+            }
+        }
+
+        for id in finished {
+            self.pointer_tweens.remove(&id);
+            self.stride_indicators.remove(&id);
+        }
+    }
+
+    // Aritmética de punteros tipada: `p + offset` avanza offset * stride_bytes bytes dentro del bloque destino
+    #[wasm_bindgen]
+    pub fn animate_pointer_arithmetic(&mut self, pointer_id: &str, offset: i32, duration_ms: f64) -> Result<(), JsValue> {
+        Ok(self.pointer_arithmetic(pointer_id, offset, duration_ms)?)
+    }
+
+    fn pointer_arithmetic(&mut self, pointer_id: &str, offset: i32, duration_ms: f64) -> Result<(), EngineError> {
+        self.ensure_unlocked(pointer_id)?;
+        self.ensure_rebindable(pointer_id)?;
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| EngineError(format!("Pointer not found: {}", pointer_id)))?;
+        if pointer.pinned {
+            return Ok(());
+        }
+        let block = self
+            .target_of(pointer)
+            .and_then(|id| self.memory_blocks.get(id))
+            .ok_or_else(|| EngineError(format!("Pointer has no target block: {}", pointer_id)))?;
+        let size = block
+            .size_bytes
+            .filter(|size| *size > 0)
+            .ok_or_else(|| EngineError(format!("Block {} has no size_bytes", block.id)))?;
+
+        let stride = pointer.stride_bytes.unwrap_or(1);
+        let delta_bytes = offset as i64 * stride as i64;
+        let dx = delta_bytes as f64 / size as f64 * block.width;
+        let tween = PointerTween {
+            from: (pointer.end_x, pointer.end_y, pointer.end_z),
+            to: (pointer.end_x + dx, pointer.end_y, pointer.end_z),
+            elapsed_ms: 0.0,
+            duration_ms: duration_ms.max(0.0),
+            arc_height: 0.0,
+            target_block_id: pointer.target_block_id.clone(),
+        };
+
+        let pointer = self.pointers.get_mut(pointer_id).unwrap();
+        if let Some(address) = pointer.target_address {
+            pointer.target_address = Some(address.wrapping_add_signed(delta_bytes));
+        }
+        self.pointer_tweens.insert(pointer_id.to_string(), tween);
+        self.stride_indicators.insert(pointer_id.to_string(), delta_bytes);
+        if duration_ms <= 0.0 {
+            self.advance_pointer_tweens(0.0);
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Fija el reloj del motor a un instante concreto (ms)
+    #[wasm_bindgen]
+    pub fn set_manual_time(&mut self, time_ms: f64) {
+        if self.manual_time.is_none() {
+            self.last_frame_time = time_ms;
+        }
+        self.manual_time = Some(time_ms);
+    }
+
+    #[wasm_bindgen]
+    pub fn use_real_time(&mut self) {
+        self.manual_time = None;
+        self.last_frame_time = self.now();
+    }
+
+    fn now(&self) -> f64 {
+        self.manual_time.unwrap_or_else(performance_now)
+    }
+
+    // Interpola el valor numérico de un bloque hasta `target` en `duration_ms`
+    #[wasm_bindgen]
+    pub fn animate_value_to(&mut self, block_id: &str, target: f64, duration_ms: f64) -> Result<(), JsValue> {
+        let block = self
+            .memory_blocks
+            .get_mut(block_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))?;
+
+        let current_text = block.value.clone().unwrap_or_default();
+        let from: f64 = current_text
+            .trim()
+            .parse()
+            .map_err(|_| JsValue::from_str(&format!("Block {} has no numeric value", block_id)))?;
+
+        let tween = ValueTween {
+            from,
+            to: target,
+            elapsed_ms: 0.0,
+            duration_ms: duration_ms.max(0.0),
+            decimals: decimal_places(current_text.trim()).max(decimal_places(&target.to_string())),
+        };
+
+        if tween.duration_ms == 0.0 {
+            block.value = Some(tween.format(target));
+        } else {
+            self.value_tweens.insert(block_id.to_string(), tween);
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    fn advance_value_tweens(&mut self, delta_ms: f64) {
+        let mut finished = Vec::new();
+
+        for (id, tween) in self.value_tweens.iter_mut() {
+            tween.elapsed_ms += delta_ms;
+
+            if let Some(block) = self.memory_blocks.get_mut(id) {
+                block.value = Some(tween.format(tween.current()));
+            }
+            if tween.elapsed_ms >= tween.duration_ms {
+                finished.push(id.clone());
+            }
+        }
+
+        for id in finished {
+            self.value_tweens.remove(&id);
+        }
+    }
+
+    fn advance_color_tweens(&mut self, delta_ms: f64) {
+        if self.color_tweens.is_empty() {
+            return;
+        }
+        let mut finished = Vec::new();
+
+        for (id, tween) in self.color_tweens.iter_mut() {
+            tween.elapsed_ms += delta_ms;
+
+            let color = if tween.elapsed_ms >= tween.duration_ms {
+                finished.push(id.clone());
+                tween.target.clone()
+            } else {
+                tween.current()
+            };
+            if let Some(block) = self.memory_blocks.get_mut(id) {
+                block.color = color;
+            } else if let Some(pointer) = self.pointers.get_mut(id) {
+                pointer.color = color;
+            }
+        }
+
+        for id in finished {
+            self.color_tweens.remove(&id);
+        }
+        self.mark_dirty();
+    }
+
+    // Duración de las transiciones de set_color; 0 cambia el color al instante
+    #[wasm_bindgen]
+    pub fn set_color_transition(&mut self, duration_ms: f64) {
+        self.color_transition_ms = duration_ms.max(0.0);
+    }
+
+    // Cambia el color de un bloque o puntero, con transición si está configurada
+    #[wasm_bindgen]
+    pub fn set_color(&mut self, id: &str, color: &str) -> Result<(), JsValue> {
+        let current = self
+            .memory_blocks
+            .get(id)
+            .map(|block| block.color.clone())
+            .or_else(|| self.pointers.get(id).map(|pointer| pointer.color.clone()))
+            .ok_or_else(|| JsValue::from_str(&format!("Object not found: {}", id)))?;
+
+        // Se parte del color mostrado, aunque haya otra transición a medias
+        self.color_tweens.remove(id);
+        match (parse_color(&current), parse_color(color)) {
+            (Some(from), Some(to)) if self.color_transition_ms > 0.0 => {
+                let tween = ColorTween {
+                    from,
+                    to,
+                    target: color.to_string(),
+                    elapsed_ms: 0.0,
+                    duration_ms: self.color_transition_ms,
+                };
+                self.color_tweens.insert(id.to_string(), tween);
+            }
+            _ => {
+                if let Some(block) = self.memory_blocks.get_mut(id) {
+                    block.color = color.to_string();
+                } else if let Some(pointer) = self.pointers.get_mut(id) {
+                    pointer.color = color.to_string();
+                }
+            }
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Color mostrado actualmente (a mitad de transición si hay una en curso)
+    #[wasm_bindgen]
+    pub fn get_color(&self, id: &str) -> Result<String, JsValue> {
+        self.memory_blocks
+            .get(id)
+            .map(|block| block.color.clone())
+            .or_else(|| self.pointers.get(id).map(|pointer| pointer.color.clone()))
+            .ok_or_else(|| JsValue::from_str(&format!("Object not found: {}", id)))
+    }
+
+    #[wasm_bindgen]
+    pub fn render(&self, canvas_id: &str) -> Result<(), JsValue> {
+        let (canvas, context) = canvas_by_id(canvas_id)?;
+
+        self.prepare_surface(&context, canvas.width() as f64, canvas.height() as f64);
+
+        self.draw_scene(&context, &self.camera, &self.projection)?;
+        self.draw_overlays(&context, canvas.width() as f64, canvas.height() as f64)
+    }
+
+    // Barra de uso del heap: un segmento por bloque vivo (proporcional a size_bytes) y el resto libre.
+    // Devuelve la fracción de la arena ocupada
+    #[wasm_bindgen]
+    pub fn render_utilization_bar(&self, canvas_id: &str, x: f64, y: f64, w: f64, h: f64, arena_bytes: f64) -> Result<f64, JsValue> {
+        if arena_bytes <= 0.0 || !arena_bytes.is_finite() {
+            return Err(JsValue::from_str(&format!("Invalid arena size: {}", arena_bytes)));
+        }
+        let (_, context) = canvas_by_id(canvas_id)?;
+        let dpr = self.device_pixel_ratio;
+        context.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0)?;
+
+        // Espacio libre de fondo
+        context.clear_rect(x, y, w, h);
+        context.set_fill_style_str("#2C3E50");
+        context.fill_rect(x, y, w, h);
+
+        let (segments, fraction) = self.utilization_segments(w, arena_bytes);
+        for (start, end, color) in segments {
+            context.set_fill_style_str(color);
+            context.fill_rect(x + start, y, end - start, h);
+            context.set_stroke_style_str("white");
+            self.set_stroke_width(&context, 1.0);
+            context.stroke_rect(x + start, y, end - start, h);
+        }
+
+        context.set_stroke_style_str("#888888");
+        self.set_stroke_width(&context, 1.0);
+        context.stroke_rect(x, y, w, h);
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        Ok(fraction)
+    }
+
+    // Segmentos (inicio, fin, color) de la barra de uso sobre un ancho w y fracción ocupada de la arena
+    fn utilization_segments(&self, w: f64, arena_bytes: f64) -> (Vec<(f64, f64, &str)>, f64) {
+        let mut blocks: Vec<&MemoryBlock3D> = self
+            .memory_blocks
+            .values()
+            .filter(|b| b.memory_type == "heap" && b.freed_at.is_none() && b.size_bytes.is_some())
+            .collect();
+        blocks.sort_by(|a, b| a.address.cmp(&b.address).then_with(|| a.id.cmp(&b.id)));
+
+        let mut used = 0.0;
+        let mut segments = Vec::new();
+        for block in blocks {
+            let start = (used / arena_bytes).min(1.0) * w;
+            used += block.size_bytes.unwrap_or(0) as f64;
+            let end = (used / arena_bytes).min(1.0) * w;
+            if end > start {
+                segments.push((start, end, block.color.as_str()));
+            }
+        }
+        (segments, used / arena_bytes)
+    }
+
+    // Diapositiva "antes/después": dos escenas independientes, cada una ajustada a su mitad del canvas
+    #[wasm_bindgen]
+    pub fn render_split(&self, canvas_id: &str, left_json: &str, right_json: &str) -> Result<(), JsValue> {
+        let (canvas, context) = canvas_by_id(canvas_id)?;
+        let (canvas_width, canvas_height) = (canvas.width() as f64, canvas.height() as f64);
+        let dpr = self.device_pixel_ratio;
+        let (half_width, height) = (canvas_width / dpr / 2.0, canvas_height / dpr);
+
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        self.prepare_surface(&context, canvas_width, canvas_height);
+
+        for (index, json) in [left_json, right_json].into_iter().enumerate() {
+            let mut half = self.split_engine(json)?;
+            half.fit_to_canvas(half_width, height, SPLIT_PADDING);
+            let offset_x = half_width * index as f64;
+            let camera = Camera { pan_x: half.camera.pan_x + offset_x, ..half.camera };
+
+            // Recortar a la mitad para que ninguna escena invada la otra
+            context.save();
+            context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+            context.begin_path();
+            context.rect(offset_x * dpr, 0.0, half_width * dpr, canvas_height);
+            context.clip();
+            let result = half.draw_scene(&context, &camera, &half.projection);
+            context.restore();
+            result?;
+        }
+
+        // Separador central
+        context.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0)?;
+        context.set_stroke_style_str("#888888");
+        self.set_stroke_width(&context, 1.0);
+        context.begin_path();
+        context.move_to(half_width, 0.0);
+        context.line_to(half_width, height);
+        context.stroke();
+        Ok(())
+    }
+
+    // Motor temporal con una escena importada y la misma configuración visual que este
+    fn split_engine(&self, json: &str) -> Result<AnimationEngine, EngineError> {
+        let mut engine = Self::with_clock(self.manual_time);
+        engine.projection = self.projection;
+        engine.device_pixel_ratio = self.device_pixel_ratio;
+        engine.hairline = self.hairline;
+        engine.render_3d_boxes = self.render_3d_boxes;
+        engine.load_scene(json)?;
+        Ok(engine)
+    }
+
+    // Crea una vista extra ({ zoom, pan_x, pan_y, projection? }) que comparte la escena; devuelve su id
+    #[wasm_bindgen]
+    pub fn create_view(&mut self, camera: JsValue) -> Result<String, JsValue> {
+        let view = self.parse_view(camera)?;
+        Ok(self.add_view(view))
+    }
+
+    fn add_view(&mut self, view: (Camera, Option<ProjectionMatrix>)) -> String {
+        self.next_view_id += 1;
+        let id = format!("view-{}", self.next_view_id);
+        self.views.insert(id.clone(), view);
+        id
+    }
+
+    #[wasm_bindgen]
+    pub fn update_view(&mut self, view_id: &str, camera: JsValue) -> Result<(), JsValue> {
+        if !self.views.contains_key(view_id) {
+            return Err(JsValue::from_str(&format!("View not found: {}", view_id)));
+        }
+        let view = self.parse_view(camera)?;
+        self.views.insert(view_id.to_string(), view);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_view(&mut self, view_id: &str) {
+        self.views.remove(view_id);
+    }
+
+    // Dibuja la escena compartida en otro canvas a través de la cámara y proyección de la vista
+    #[wasm_bindgen]
+    pub fn render_view(&self, view_id: &str, canvas_id: &str) -> Result<(), JsValue> {
+        let (camera, projection) = *self
+            .views
+            .get(view_id)
+            .ok_or_else(|| JsValue::from_str(&format!("View not found: {}", view_id)))?;
+        let (canvas, context) = canvas_by_id(canvas_id)?;
+
+        self.prepare_surface(&context, canvas.width() as f64, canvas.height() as f64);
+
+        self.draw_scene(&context, &camera, projection.as_ref().unwrap_or(&self.projection))
+    }
+
+    // Posición en pantalla de un punto del mundo vista a través de una vista
+    #[wasm_bindgen]
+    pub fn view_world_to_screen(&self, view_id: &str, x: f64, y: f64, z: f64) -> Result<JsValue, JsValue> {
+        let (x, y) = self.view_point(view_id, x, y, z)?;
+        to_js(&Point2D { x, y })
+    }
+
+    fn view_point(&self, view_id: &str, x: f64, y: f64, z: f64) -> Result<(f64, f64), EngineError> {
+        let (camera, projection) = self
+            .views
+            .get(view_id)
+            .ok_or_else(|| EngineError(format!("View not found: {}", view_id)))?;
+        let (px, py) = apply_projection(projection.as_ref().unwrap_or(&self.projection), x, y, z);
+        Ok((px * camera.zoom + camera.pan_x, py * camera.zoom + camera.pan_y))
+    }
+
+    fn parse_view(&self, camera: JsValue) -> Result<(Camera, Option<ProjectionMatrix>), JsValue> {
+        let view: View = serde_wasm_bindgen::from_value(camera)?;
+        let projection = view.projection.as_deref().map(projection_from_coefficients).transpose()?;
+        Ok((Camera { zoom: self.clamp_zoom(view.camera.zoom), ..view.camera }, projection))
+    }
+
+    // Elementos fijos en pantalla (en píxeles CSS), dibujados encima de la escena
+    fn draw_overlays(&self, context: &CanvasRenderingContext2d, canvas_width: f64, canvas_height: f64) -> Result<(), JsValue> {
+        let dpr = self.device_pixel_ratio;
+        let (width, height) = (canvas_width / dpr, canvas_height / dpr);
+        context.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0)?;
+
+        if self.show_depth_meter {
+            self.render_depth_meter(context, width - 30.0, 20.0, height - 40.0);
+        }
+
+        self.render_title(context, width, height);
+
+        if let Some(ruler) = self.address_ruler {
+            self.render_address_ruler(context, &ruler, width);
+        }
+
+        if let Some(text) = self.cursor_readout_text() {
+            self.draw_badge(context, &text, 8.0, 8.0);
+        }
+
+        // El láser del presentador siempre va encima de todo
+        if let Some((x, y)) = self.presenter_pointer {
+            self.render_presenter_pointer(context, x, y);
+        }
+
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    // Canvas que se redibuja automáticamente cuando la escena cambia (None para desactivarlo)
+    #[wasm_bindgen]
+    pub fn set_render_target(&mut self, canvas_id: Option<String>) {
+        self.render_target = canvas_id;
+    }
+
+    // Las mutaciones dentro de un lote no redibujan hasta el end_batch más externo
+    #[wasm_bindgen]
+    pub fn begin_batch(&mut self) {
+        self.batch_depth += 1;
+    }
+
+    #[wasm_bindgen]
+    pub fn end_batch(&mut self) -> Result<(), JsValue> {
+        if self.batch_depth == 0 {
+            return Err(JsValue::from_str("end_batch called without begin_batch"));
+        }
+        self.batch_depth -= 1;
+        self.flush_render()
+    }
+
+    // Para bucles requestAnimationFrame: dibuja solo si hubo cambios y no hay lote abierto
+    #[wasm_bindgen]
+    pub fn render_if_dirty(&mut self, canvas_id: &str) -> Result<bool, JsValue> {
+        if !self.needs_render() {
+            return Ok(false);
+        }
+        self.render(canvas_id)?;
+        self.dirty = false;
+        self.render_count += 1;
+        Ok(true)
+    }
+
+    // Número de redibujados provocados por cambios en la escena
+    #[wasm_bindgen]
+    pub fn get_render_count(&self) -> u32 {
+        self.render_count
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+        if let Err(error) = self.flush_render() {
+            console::error_1(&error);
+        }
+    }
+
+    // Hay cambios sin dibujar y ningún lote abierto que los retenga
+    fn needs_render(&self) -> bool {
+        self.dirty && self.batch_depth == 0
+    }
+
+    fn flush_render(&mut self) -> Result<(), JsValue> {
+        if self.batch_depth > 0 {
+            return Ok(());
+        }
+        if let Some(canvas_id) = self.render_target.clone() {
+            self.render_if_dirty(&canvas_id)?;
+        }
+        Ok(())
+    }
+
+    // Exporta un único objeto recortado a sus límites proyectados más un margen
+    #[wasm_bindgen]
+    pub fn export_object_png(&self, id: &str, padding: f64) -> Result<String, JsValue> {
+        let crop = self
+            .object_crop(id, padding)
+            .ok_or_else(|| JsValue::from_str(&format!("Object not found: {}", id)))?;
+
+        let (canvas, context) = create_offscreen_canvas(crop.width, crop.height)?;
+        context.set_transform(1.0, 0.0, 0.0, 1.0, crop.offset_x, crop.offset_y)?;
+
+        if let Some(block) = self.memory_blocks.get(id) {
+            self.render_memory_block(&context, block, &self.projection);
+        } else if let Some(pointer) = self.pointers.get(id) {
+            self.render_pointer(&context, pointer, &self.projection);
+        }
+        canvas.to_data_url()
+    }
+
+    fn object_crop(&self, id: &str, padding: f64) -> Option<ObjectCrop> {
+        let (x, y, width, height) = self.object_rect(id)?;
+        let padding = padding.max(0.0);
+        Some(ObjectCrop {
+            width: (width + padding * 2.0).ceil() as u32,
+            height: (height + padding * 2.0).ceil() as u32,
+            offset_x: padding - x,
+            offset_y: padding - y,
+        })
+    }
+
+    // Miniatura PNG de lo que encuadra la cámara en el canvas dado (la escena y el título, sin otras superposiciones)
+    #[wasm_bindgen]
+    pub fn export_viewport_png(&self, canvas_id: &str) -> Result<String, JsValue> {
+        let (visible, _) = canvas_by_id(canvas_id)?;
+        let (canvas, context) = create_offscreen_canvas(visible.width(), visible.height())?;
+        self.prepare_surface(&context, canvas.width() as f64, canvas.height() as f64);
+
+        // Solo se dibuja lo que cae dentro del encuadre; el resto de la escena no llega a la miniatura
+        let dpr = self.device_pixel_ratio;
+        let framed = self.objects_in_viewport(canvas.width() as f64 / dpr, canvas.height() as f64 / dpr, |text, font| {
+            measure_text_extent(&context, text, font)
+        })?;
+        self.draw_framed_scene(&context, &self.camera, &self.projection, Some(&framed))?;
+        context.set_transform(dpr, 0.0, 0.0, dpr, 0.0, 0.0)?;
+        self.render_title(&context, canvas.width() as f64 / dpr, canvas.height() as f64 / dpr);
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+        canvas.to_data_url()
+    }
+
+    // Bloques y punteros visibles cuya huella (ver object_footprint) entra, al menos en parte, en el encuadre
+    // de la cámara de width x height píxeles CSS
+    fn objects_in_viewport(
+        &self,
+        width: f64,
+        height: f64,
+        mut measure: impl FnMut(&str, &str) -> Result<TextExtent, JsValue>,
+    ) -> Result<BTreeSet<&str>, JsValue> {
+        let ids = self.memory_blocks.keys().chain(self.pointers.values().filter(|p| p.visible).map(|p| &p.id));
+        let mut framed = BTreeSet::new();
+        for id in ids {
+            let rect = self.object_footprint(id, &mut measure)?;
+            let margin = VIEWPORT_MARGIN;
+            if rect.x + rect.width > -margin && rect.y + rect.height > -margin && rect.x < width + margin && rect.y < height + margin
+            {
+                framed.insert(id.as_str());
+            }
+        }
+        Ok(framed)
+    }
+
+    // Título fijo del diagrama ("top", "bottom", "top-left"...); null o vacío lo quita
+    #[wasm_bindgen]
+    pub fn set_title(&mut self, text: Option<String>, position: &str) -> Result<(), JsValue> {
+        let position =
+            TitlePosition::parse(position).ok_or_else(|| JsValue::from_str(&format!("Unknown title position: {}", position)))?;
+        self.title = text.filter(|text| !text.is_empty()).map(|text| (text, position));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Dibuja el título en píxeles CSS, fuera de la transformación de la cámara
+    fn render_title(&self, context: &CanvasRenderingContext2d, width: f64, height: f64) {
+        let Some((ref text, position)) = self.title else {
+            return;
+        };
+        let (x, y, align) = position.anchor(width, height);
+        context.save();
+        context.set_font(&title_font());
+        context.set_text_align(align);
+        context.set_fill_style_str("white");
+        context.fill_text(text, x, y).unwrap();
+        context.restore();
+    }
+
+    // Diagrama vectorial de lo que encuadra la cámara en un lienzo de width x height (píxeles CSS)
+    #[wasm_bindgen]
+    pub fn export_svg(&self, width: f64, height: f64) -> String {
+        let camera = &self.camera;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width,
+            h = height
+        );
+        svg.push_str(&format!(
+            "<g transform=\"matrix({} 0 0 {} {} {})\" font-family=\"Arial\">\n",
+            camera.zoom, camera.zoom, camera.pan_x, camera.pan_y
+        ));
+
+        let scene = self.to_scene();
+        for block in &scene.memory_blocks {
+            let (x, y, w, h) = self.block_rect(block);
+            // Mismo estilo que render_memory_block: atenuado si está liberado y discontinuo
+            // también para los parámetros de tipo
+            let freed = block.freed_at.is_some();
+            if freed {
+                svg.push_str("<g opacity=\"0.5\">\n");
+            }
+            let dash = if freed || block.type_param.is_some() { " stroke-dasharray=\"6 4\"" } else { "" };
+            if self.render_3d_boxes && block.depth > 0.0 {
+                for (corners, color) in box_faces(block, &self.projection) {
+                    let points: Vec<String> = corners.iter().map(|(px, py)| format!("{},{}", px, py)).collect();
+                    svg.push_str(&format!(
+                        "<polygon points=\"{}\" fill=\"{}\" stroke=\"white\" stroke-width=\"{}\"{}/>\n",
+                        points.join(" "),
+                        xml_escape(&color),
+                        self.effective_line_width(1.0),
+                        dash
+                    ));
+                }
+            }
+            let fill = if block.moved_from { MOVED_FROM_COLOR } else { &block.color };
+            let (border_color, border_width) = block_border(block);
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{}/>\n",
+                x,
+                y,
+                w,
+                h,
+                xml_escape(fill),
+                border_color,
+                self.effective_line_width(border_width),
+                dash
+            ));
+            if let Some((text, font)) = block_label(block) {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" {} fill=\"{}\">{}</text>\n",
+                    x + BLOCK_LABEL_OFFSET.0,
+                    y + BLOCK_LABEL_OFFSET.1,
+                    svg_font_attributes(font),
+                    xml_escape(block_label_color(block)),
+                    xml_escape(text)
+                ));
+            }
+            if freed {
+                svg.push_str("</g>\n");
+            }
+        }
+
+        for pointer in scene.pointers.iter().filter(|p| p.visible) {
+            let start = self.project(pointer.start_x, pointer.start_y, pointer.start_z);
+            let end = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+            // Pendientes y referencias con la misma geometría que render_pointer
+            if pointer.pending {
+                let ((tip_x, tip_y), (mark_x, mark_y)) = pending_terminus(start, end);
+                svg.push_str(&format!(
+                    "<path d=\"M {} {} L {} {}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-dasharray=\"5 4\"/>\n",
+                    start.0,
+                    start.1,
+                    tip_x,
+                    tip_y,
+                    xml_escape(&pointer.color),
+                    self.effective_line_width(pointer.thickness)
+                ));
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" {} fill=\"{}\">?</text>\n",
+                    mark_x,
+                    mark_y,
+                    svg_font_attributes(PENDING_MARK_FONT),
+                    xml_escape(&pointer.color)
+                ));
+                continue;
+            }
+            if pointer.kind == PointerKind::Reference {
+                let lines: Vec<String> = reference_binding_lines(pointer, start, end)
+                    .iter()
+                    .map(|(from, to)| format!("M {} {} L {} {}", from.0, from.1, to.0, to.1))
+                    .collect();
+                svg.push_str(&format!(
+                    "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                    lines.join(" "),
+                    xml_escape(&pointer.color),
+                    self.effective_line_width(reference_line_width(pointer))
+                ));
+                continue;
+            }
+            let control = self.curve_control_point(&self.projection, pointer, start, end);
+            let (color, head_color) = self.pointer_colors(pointer);
+            let (path, from) = match control {
+                Some((cx, cy)) => (format!("M {} {} Q {} {} {} {}", start.0, start.1, cx, cy, end.0, end.1), (cx, cy)),
+                None => (format!("M {} {} L {} {}", start.0, start.1, end.0, end.1), start),
+            };
+            svg.push_str(&format!(
+                "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                path,
+                xml_escape(&color),
+                self.effective_line_width(self.line_thickness(pointer))
+            ));
+            let [(x1, y1), (x2, y2)] = arrow_head_points(end.0, end.1, from.0, from.1);
+            svg.push_str(&format!(
+                "<path d=\"M {x1} {y1} L {} {} L {x2} {y2}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+                end.0,
+                end.1,
+                xml_escape(&head_color),
+                self.effective_line_width(self.line_thickness(pointer))
+            ));
+        }
+        svg.push_str("</g>\n");
+
+        if let Some((ref text, position)) = self.title {
+            let (x, y, align) = position.anchor(width, height);
+            let anchor = match align {
+                "left" => "start",
+                "center" => "middle",
+                _ => "end",
+            };
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" {} fill=\"white\" text-anchor=\"{}\">{}</text>\n",
+                x,
+                y,
+                svg_font_attributes(&title_font()),
+                anchor,
+                xml_escape(text)
+            ));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    // Rectángulo proyectado de un bloque o puntero
+    fn object_rect(&self, id: &str) -> Option<(f64, f64, f64, f64)> {
+        self.object_rect_in(&self.projection, id)
+    }
+
+    fn object_rect_in(&self, projection: &ProjectionMatrix, id: &str) -> Option<(f64, f64, f64, f64)> {
+        if let Some(block) = self.memory_blocks.get(id) {
+            return Some(projected_rect(projection, block));
+        }
+        let pointer = self.pointers.get(id)?;
+        let (x1, y1) = apply_projection(projection, pointer.start_x, pointer.start_y, pointer.start_z);
+        let (x2, y2) = apply_projection(projection, pointer.end_x, pointer.end_y, pointer.end_z);
+        Some((x1.min(x2), y1.min(y2), (x1 - x2).abs(), (y1 - y2).abs()))
+    }
+
+    // Escala de un bloque que está apareciendo (1 si ya está del todo)
+    fn block_growth(&self, id: &str) -> f64 {
+        self.block_growths.get(id).map_or(1.0, |g| 1.0 - g.remaining())
+    }
+
+    // Dibuja la escena completa a través de una cámara y una proyección
+    fn draw_scene(&self, context: &CanvasRenderingContext2d, camera: &Camera, projection: &ProjectionMatrix) -> Result<(), JsValue> {
+        self.draw_framed_scene(context, camera, projection, None)
+    }
+
+    // Como draw_scene, pero con `framed` solo se dibujan los bloques y punteros de ese conjunto
+    fn draw_framed_scene(
+        &self,
+        context: &CanvasRenderingContext2d,
+        camera: &Camera,
+        projection: &ProjectionMatrix,
+        framed: Option<&BTreeSet<&str>>,
+    ) -> Result<(), JsValue> {
+        let is_framed = |id: &str| framed.is_none_or(|framed| framed.contains(id));
+        // La cámara trabaja en píxeles CSS; el canvas, en píxeles físicos
+        let dpr = self.device_pixel_ratio;
+        context.set_transform(camera.zoom * dpr, 0.0, 0.0, camera.zoom * dpr, camera.pan_x * dpr, camera.pan_y * dpr)?;
+
+        if let Some(axis) = self.time_axis {
+            self.render_time_axis(context, &axis);
+        }
+
+        // Renderizar bloques de memoria
+        for block in self.memory_blocks.values().filter(|block| is_framed(&block.id)) {
+            let fade = self.block_fades.get(&block.id).map_or(1.0, Effect::remaining);
+            let growth = self.block_growth(&block.id);
+            context.set_global_alpha(self.spotlight_alpha(&block.id) * fade * growth);
+            if growth < 1.0 {
+                // Escalar alrededor del centro mientras el bloque aparece
+                let (x, y, w, h) = projected_rect(projection, block);
+                let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+                context.save();
+                context.translate(cx, cy)?;
+                context.scale(growth, growth)?;
+                context.translate(-cx, -cy)?;
+                self.render_memory_block(context, block, projection);
+                context.restore();
+            } else {
+                self.render_memory_block(context, block, projection);
+            }
+        }
+
+        // Renderizar punteros
+        for pointer in self.pointers.values().filter(|p| p.visible && is_framed(&p.id)) {
+            let fade = self.pointer_fades.get(&pointer.id).map_or(1.0, Effect::remaining);
+            context.set_global_alpha(self.spotlight_alpha(&pointer.id) * fade);
+            self.render_pointer(context, pointer, projection);
+        }
+        context.set_global_alpha(1.0);
+
+        for comparison in &self.comparisons {
+            self.render_comparison(context, comparison, projection);
+        }
+
+        for ((x, y), effect) in &self.crash_bursts {
+            self.render_crash_burst(context, *x, *y, effect.remaining());
+        }
+
+        if let Some(rect) = self.selected_id.as_deref().and_then(|id| self.object_rect_in(projection, id)) {
+            self.render_selection(context, rect);
+        }
+        if self.show_focus_order {
+            self.render_focus_order(context, projection);
+        }
+
+        context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)?;
+
+        Ok(())
+    }
+
+    // Selecciona un objeto (o ninguno con null)
+    #[wasm_bindgen]
+    pub fn select(&mut self, id: Option<String>) -> Result<(), JsValue> {
+        if let Some(ref id) = id {
+            if self.object_rect(id).is_none() {
+                return Err(JsValue::from_str(&format!("Object not found: {}", id)));
+            }
+        }
+        self.selected_id = id;
+        self.record_gesture();
+        let selected = self.selected_id.clone().unwrap_or_default();
+        self.emit_event("selection_changed", &selected);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_selected(&self) -> Option<String> {
+        self.selected_id.clone()
+    }
+
+    // Avanza la selección al siguiente objeto en orden de foco, volviendo al principio al final
+    #[wasm_bindgen]
+    pub fn select_next(&mut self) -> Option<String> {
+        let order = self.focus_ids();
+        let next = match self.selected_id.as_ref().and_then(|id| order.iter().position(|o| o == id)) {
+            Some(index) => order.get((index + 1) % order.len()).cloned(),
+            None => order.first().cloned(),
+        };
+        self.selected_id = next.clone();
+        self.record_gesture();
+        self.emit_event("selection_changed", next.as_deref().unwrap_or_default());
+        self.mark_dirty();
+        next
+    }
+
+    // Límite conjunto (bytes aproximados) para historiales de ref_count, fallos registrados, la
+    // traza de interacciones y los gestos grabados; al superarlo se descartan las entradas más antiguas. 0 lo desactiva
+    #[wasm_bindgen]
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = (bytes > 0).then_some(bytes);
+        self.enforce_memory_budget();
+    }
+
+    #[wasm_bindgen]
+    pub fn retained_bytes(&self) -> usize {
+        let refcounts: usize = self.refcount_history.values().map(|h| h.len() * REFCOUNT_ENTRY_BYTES).sum();
+        let derefs: usize = self.null_derefs.iter().map(null_deref_bytes).sum();
+        let trace: usize = self.trace.iter().map(trace_entry_bytes).sum();
+        let gestures: usize = self
+            .gesture_recording
+            .as_ref()
+            .map(|(_, gestures)| gestures.iter().map(gesture_bytes).sum())
+            .unwrap_or(0);
+        refcounts + derefs + trace + gestures
+    }
+
+    fn enforce_memory_budget(&mut self) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        let mut retained = self.retained_bytes();
+
+        while retained > budget {
+            // Entrada más antigua (según el reloj del motor) al frente de cada buffer
+            let oldest_refcount = self
+                .refcount_history
+                .iter()
+                .filter_map(|(id, history)| history.front().map(|(time, _)| (*time, id.clone())))
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            let oldest_deref = self.null_derefs.front().map(|d| d.time_ms);
+            let oldest_trace = self.trace.front().map(|t| t.time_ms);
+            let oldest_gesture = self
+                .gesture_recording
+                .as_ref()
+                .and_then(|(started_at, gestures)| gestures.first().map(|g| started_at + g.time_ms));
+
+            let candidates = [
+                oldest_refcount.as_ref().map(|(time, _)| *time),
+                oldest_deref,
+                oldest_trace,
+                oldest_gesture,
+            ];
+            let Some((index, _)) = candidates
+                .iter()
+                .enumerate()
+                .filter_map(|(i, time)| time.map(|t| (i, t)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+            else {
+                break;
+            };
+
+            retained -= match index {
+                0 => {
+                    let (_, id) = oldest_refcount.unwrap();
+                    let history = self.refcount_history.get_mut(&id).unwrap();
+                    history.pop_front();
+                    if history.is_empty() {
+                        self.refcount_history.remove(&id);
+                    }
+                    REFCOUNT_ENTRY_BYTES
+                }
+                1 => self.null_derefs.pop_front().as_ref().map(null_deref_bytes).unwrap_or(0),
+                2 => self.trace.pop_front().as_ref().map(trace_entry_bytes).unwrap_or(0),
+                _ => {
+                    let (_, gestures) = self.gesture_recording.as_mut().unwrap();
+                    gesture_bytes(&gestures.remove(0))
+                }
+            };
+        }
+    }
+
+    // Callback de JS que recibe los eventos del motor ({ type, id, time_ms })
+    #[wasm_bindgen]
+    pub fn set_event_callback(&mut self, callback: Option<js_sys::Function>) {
+        self.event_callback = callback.map(EventSink::Callback);
+        self.pending_events.clear();
+    }
+
+    // Agrupa los eventos del mismo tipo dentro de la ventana en una sola llamada con un array; 0 desactiva
+    #[wasm_bindgen]
+    pub fn set_event_coalescing(&mut self, window_ms: f64) {
+        self.flush_events();
+        self.event_coalescing_ms = window_ms.max(0.0);
+    }
+
+    // Entrega ya todos los eventos agrupados pendientes
+    #[wasm_bindgen]
+    pub fn flush_events(&mut self) {
+        for (_, batch) in std::mem::take(&mut self.pending_events) {
+            self.deliver_batch(batch);
+        }
+    }
+
+    fn emit_event(&mut self, kind: &'static str, id: &str) {
+        if self.event_callback.is_none() {
+            return;
+        }
+        let event = EngineEvent { kind, id: id.to_string(), time_ms: self.now() };
+
+        if self.event_coalescing_ms <= 0.0 {
+            self.dispatch(&event);
+            return;
+        }
+
+        // Un evento fuera de la ventana del lote abierto lo cierra antes de empezar otro
+        let window = self.event_coalescing_ms;
+        if self.pending_events.get(kind).is_some_and(|batch| event.time_ms - batch[0].time_ms >= window) {
+            if let Some(batch) = self.pending_events.remove(kind) {
+                self.deliver_batch(batch);
+            }
+        }
+        self.pending_events.entry(kind).or_default().push(event);
+    }
+
+    fn flush_due_events(&mut self, now: f64) {
+        let window = self.event_coalescing_ms;
+        let due: Vec<&'static str> = self
+            .pending_events
+            .iter()
+            .filter(|(_, batch)| now - batch[0].time_ms >= window)
+            .map(|(kind, _)| *kind)
+            .collect();
+        for kind in due {
+            if let Some(batch) = self.pending_events.remove(kind) {
+                self.deliver_batch(batch);
+            }
+        }
+    }
+
+    fn deliver_batch(&self, batch: Vec<EngineEvent>) {
+        self.dispatch(&batch);
+    }
+
+    // El callback se ejecuta en una microtarea, cuando el método del motor ya ha devuelto el
+    // control: así puede volver a llamar al motor sin chocar con el préstamo mutable en curso
+    fn dispatch<T: serde::Serialize>(&self, payload: &T) {
+        match self.event_callback.clone() {
+            Some(EventSink::Callback(callback)) => {
+                if let Ok(value) = to_js(payload) {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let _ = callback.call1(&JsValue::NULL, &value);
+                    });
+                }
+            }
+            #[cfg(test)]
+            Some(EventSink::Recorder(deliveries)) => deliveries.borrow_mut().push(serde_json::to_string(payload).unwrap()),
+            None => {}
+        }
+    }
+
+    // Graba los cambios de cámara y selección con su instante para reproducirlos después
+    #[wasm_bindgen]
+    pub fn start_gesture_recording(&mut self) {
+        let initial = Gesture { time_ms: 0.0, camera: self.camera, selected_id: self.selected_id.clone() };
+        self.gesture_recording = Some((self.now(), vec![initial]));
+    }
+
+    // Termina la grabación y devuelve los gestos en JSON (vacío si no se estaba grabando)
+    #[wasm_bindgen]
+    pub fn stop_gesture_recording(&mut self) -> String {
+        let gestures = self.gesture_recording.take().map(|(_, gestures)| gestures).unwrap_or_default();
+        serde_json::to_string(&gestures).unwrap()
+    }
+
+    // Reaplica una grabación a lo largo del tiempo; speed 2.0 la reproduce al doble de velocidad
+    #[wasm_bindgen]
+    pub fn replay_gestures(&mut self, json: &str, speed: f64) -> Result<(), JsValue> {
+        if !(speed > 0.0 && speed.is_finite()) {
+            return Err(JsValue::from_str(&format!("Invalid replay speed: {}", speed)));
+        }
+        let gestures: Vec<Gesture> = serde_json::from_str(json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid gesture JSON: {}", e)))?;
+
+        self.gesture_replay = Some(GestureReplay { gestures: gestures.into(), elapsed_ms: 0.0, speed });
+        self.advance_gesture_replay(0.0);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn is_replaying_gestures(&self) -> bool {
+        self.gesture_replay.is_some()
+    }
+
+    fn record_gesture(&mut self) {
+        let now = self.now();
+        if let Some((started_at, gestures)) = self.gesture_recording.as_mut() {
+            gestures.push(Gesture { time_ms: now - *started_at, camera: self.camera, selected_id: self.selected_id.clone() });
+        }
+        self.enforce_memory_budget();
+    }
+
+    fn advance_gesture_replay(&mut self, delta_ms: f64) {
+        let Some(replay) = self.gesture_replay.as_mut() else {
+            return;
+        };
+        replay.elapsed_ms += delta_ms * replay.speed;
+
+        let mut applied = None;
+        while replay.gestures.front().is_some_and(|g| g.time_ms <= replay.elapsed_ms) {
+            applied = replay.gestures.pop_front();
+        }
+        if replay.gestures.is_empty() {
+            self.gesture_replay = None;
+        }
+
+        if let Some(gesture) = applied {
+            self.camera = gesture.camera;
+            self.selected_id = gesture.selected_id.filter(|id| self.object_rect(id).is_some());
+            self.mark_dirty();
+        }
+    }
+
+    // Orden de recorrido con select_next: de arriba abajo y de izquierda a derecha
+    #[wasm_bindgen]
+    pub fn focus_order(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.focus_ids())
+    }
+
+    #[wasm_bindgen]
+    pub fn show_focus_order(&mut self, enabled: bool) {
+        self.show_focus_order = enabled;
+        self.mark_dirty();
+    }
+
+    fn clear_selection_of(&mut self, id: &str) {
+        if self.selected_id.as_deref() == Some(id) {
+            self.selected_id = None;
+            self.emit_event("selection_changed", "");
+        }
+    }
+
+    fn focus_ids(&self) -> Vec<String> {
+        let mut entries: Vec<(f64, f64, &str)> = self
+            .memory_blocks
+            .values()
+            .map(|block| {
+                let (x, y, _, _) = self.block_rect(block);
+                (y, x, block.id.as_str())
+            })
+            .chain(self.pointers.values().filter(|p| p.visible).map(|pointer| {
+                let (x, y) = self.project(pointer.start_x, pointer.start_y, pointer.start_z);
+                (y, x, pointer.id.as_str())
+            }))
+            .collect();
+
+        entries.sort_by(|a, b| {
+            a.0.round()
+                .total_cmp(&b.0.round())
+                .then(a.1.total_cmp(&b.1))
+                .then_with(|| a.2.cmp(b.2))
+        });
+        entries.into_iter().map(|(_, _, id)| id.to_string()).collect()
+    }
+
+    // Resalta los bloques cuyo valor cumple el predicado JS y atenúa el resto
+    #[wasm_bindgen]
+    pub fn highlight_where_value(&mut self, predicate: &js_sys::Function) -> Result<usize, JsValue> {
+        self.highlight_matching(|value| {
+            let value = value.map_or(JsValue::NULL, JsValue::from_str);
+            Ok(predicate.call1(&JsValue::NULL, &value)?.is_truthy())
+        })
+    }
+
+    fn highlight_matching(&mut self, mut predicate: impl FnMut(Option<&str>) -> Result<bool, JsValue>) -> Result<usize, JsValue> {
+        let mut matches = HashSet::new();
+
+        for block in self.memory_blocks.values() {
+            if predicate(block.value.as_deref())? {
+                matches.insert(block.id.clone());
+            }
+        }
+
+        let count = matches.len();
+        self.highlighted = Some(matches);
+        self.mark_dirty();
+        Ok(count)
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_highlight(&mut self) {
+        self.highlighted = None;
+        self.mark_dirty();
+    }
+
+    // Resalta todo lo alcanzable desde un puntero o bloque (punteros intermedios incluidos) y atenúa el resto
+    #[wasm_bindgen]
+    pub fn highlight_reachable_from(&mut self, id: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.highlight_reachable(id)?)
+    }
+
+    fn highlight_reachable(&mut self, id: &str) -> Result<Vec<String>, EngineError> {
+        if self.object_rect(id).is_none() {
+            return Err(EngineError(format!("Object not found: {}", id)));
+        }
+
+        let reachable = self.reachable_from(id);
+        let mut ids: Vec<String> = reachable.iter().cloned().collect();
+        ids.sort();
+
+        self.highlighted = Some(reachable);
+        self.mark_dirty();
+        Ok(ids)
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_reachable_highlight(&mut self) {
+        self.highlighted = None;
+        self.mark_dirty();
+    }
+
+    // Recorrido en anchura alternando puntero → bloque destino → punteros guardados en ese bloque
+    fn reachable_from(&self, id: &str) -> HashSet<String> {
+        self.reachable_order(&[id.to_string()]).into_iter().collect()
+    }
+
+    // Ids alcanzables desde varias raíces, en el orden en que los visita el recorrido en anchura
+    fn reachable_order(&self, roots: &[String]) -> Vec<String> {
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+        let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+
+        while let Some(current) = queue.pop_front() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+            order.push(current.clone());
+            if let Some(pointer) = self.pointers.get(&current) {
+                if let Some(target) = self.target_of(pointer).filter(|t| self.memory_blocks.contains_key(*t)) {
+                    queue.push_back(target.to_string());
+                }
+            } else {
+                queue.extend(self.outgoing_pointers(&current).map(|p| p.id.clone()));
+            }
+        }
+        order
+    }
+
+    #[wasm_bindgen]
+    pub fn is_highlighted(&self, id: &str) -> bool {
+        self.highlighted.as_ref().is_some_and(|ids| ids.contains(id))
+    }
+
+    fn spotlight_alpha(&self, id: &str) -> f64 {
+        match self.highlighted {
+            Some(ref ids) if !ids.contains(id) => DIMMED_ALPHA,
+            _ => 1.0,
+        }
+    }
+
+    // Muestra un bloque como rejilla de bytes hexadecimales (estilo editor hex)
+    #[wasm_bindgen]
+    pub fn render_hex_dump(&mut self, block_id: &str, bytes: JsValue, columns: usize) -> Result<(), JsValue> {
+        if !self.memory_blocks.contains_key(block_id) {
+            return Err(JsValue::from_str(&format!("Memory block not found: {}", block_id)));
+        }
+        let bytes: Vec<u8> = serde_wasm_bindgen::from_value(bytes)?;
+
+        self.hex_dumps.insert(
+            block_id.to_string(),
+            HexDump { bytes, columns: columns.max(1) },
+        );
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_hex_dump(&mut self, block_id: &str) {
+        self.hex_dumps.remove(block_id);
+        self.mark_dirty();
+    }
+
+    // Engancha el extremo del puntero al bloque más cercano dentro de `max_distance`
+    #[wasm_bindgen]
+    pub fn snap_pointer_to_nearest(&mut self, pointer_id: &str, max_distance: f64) -> Result<JsValue, JsValue> {
+        let snapped = self.snap_to_nearest(pointer_id, max_distance)?;
+        Ok(snapped.map_or(JsValue::NULL, |block_id| JsValue::from_str(&block_id)))
+    }
+
+    fn snap_to_nearest(&mut self, pointer_id: &str, max_distance: f64) -> Result<Option<String>, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        self.ensure_unlocked(pointer_id)?;
+        self.ensure_rebindable(pointer_id)?;
+        if pointer.pinned {
+            return Ok(None);
+        }
+        let (end_x, end_y) = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+
+        let nearest = self
+            .memory_blocks
+            .values()
+            .map(|block| (block, distance_to_rect(end_x, end_y, self.block_rect(block))))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(block, _)| (block.id.clone(), block_anchor(block)));
+
+        let Some((block_id, (x, y, z))) = nearest else {
+            return Ok(None);
+        };
+
+        let pointer = self.pointers.get_mut(pointer_id).unwrap();
+        pointer.end_x = x;
+        pointer.end_y = y;
+        pointer.end_z = z;
+        pointer.target_block_id = Some(block_id.clone());
+        self.mark_dirty();
+
+        Ok(Some(block_id))
+    }
+
+    // Muestra un conector entre dos punteros con la relación "==" o "!="
+    #[wasm_bindgen]
+    pub fn show_comparison(&mut self, id_a: &str, id_b: &str, relation: &str) -> Result<(), JsValue> {
+        let equal = match relation {
+            "==" => true,
+            "!=" => false,
+            other => return Err(JsValue::from_str(&format!("Unknown relation: {}", other))),
+        };
+        for id in [id_a, id_b] {
+            if !self.pointers.contains_key(id) {
+                return Err(JsValue::from_str(&format!("Pointer not found: {}", id)));
+            }
+        }
+
+        self.comparisons.retain(|c| !(c.id_a == id_a && c.id_b == id_b));
+        self.comparisons.push(PointerComparison {
+            id_a: id_a.to_string(),
+            id_b: id_b.to_string(),
+            equal,
+        });
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_comparisons(&mut self) {
+        self.comparisons.clear();
+        self.mark_dirty();
+    }
+
+    // Verde si la relación se cumple, rojo si no
+    fn comparison_color(&self, comparison: &PointerComparison) -> &'static str {
+        if self.comparison_holds(comparison) {
+            "#2ECC71"
+        } else {
+            "#E74C3C"
+        }
+    }
+
+    // Indica si la relación de la comparación se cumple según los destinos
+    fn comparison_holds(&self, comparison: &PointerComparison) -> bool {
+        let (Some(a), Some(b)) = (self.pointers.get(&comparison.id_a), self.pointers.get(&comparison.id_b)) else {
+            return false;
+        };
+
+        let same_target = match (self.target_of(a), self.target_of(b)) {
+            (Some(target_a), Some(target_b)) => target_a == target_b,
+            _ => {
+                let (ax, ay) = self.project(a.end_x, a.end_y, a.end_z);
+                let (bx, by) = self.project(b.end_x, b.end_y, b.end_z);
+                (ax - bx).abs() < 0.5 && (ay - by).abs() < 0.5
+            }
+        };
+        same_target == comparison.equal
+    }
+
+    fn target_of<'a>(&'a self, pointer: &'a Pointer3D) -> Option<&'a str> {
+        if pointer.pending {
+            return None;
+        }
+        pointer.target_block_id.as_deref().or_else(|| self.inferred_target(pointer))
+    }
+
+    // Sin destino explícito, el bloque más cercano al extremo dentro del radio de inferencia
+    fn inferred_target(&self, pointer: &Pointer3D) -> Option<&str> {
+        let radius = self.target_inference_radius?;
+        if self.is_pointer_null(pointer) {
+            return None;
+        }
+        let (x, y) = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+        self.memory_blocks
+            .values()
+            .map(|block| (block, distance_to_rect(x, y, self.block_rect(block))))
+            .filter(|(_, distance)| *distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.id.cmp(&b.0.id)))
+            .map(|(block, _)| block.id.as_str())
+    }
+
+    // Margen (píxeles) para considerar que un extremo sin target_block_id está sobre un bloque; negativo lo desactiva
+    #[wasm_bindgen]
+    pub fn set_target_inference_radius(&mut self, pixels: f64) {
+        self.target_inference_radius = (pixels >= 0.0).then_some(pixels);
+        self.mark_dirty();
+    }
+
+    // Ids de los punteros que apuntan al bloque, explícitamente o por proximidad del extremo
+    #[wasm_bindgen]
+    pub fn pointers_to_block(&self, block_id: &str) -> Result<JsValue, JsValue> {
+        let mut ids: Vec<&str> = self.pointers_targeting(block_id).map(|p| p.id.as_str()).collect();
+        ids.sort();
+        to_js(&ids)
+    }
+
+    // Punteros propietarios que mantienen vivo el bloque: quitarlos todos deja su cuenta de referencias en cero
+    #[wasm_bindgen]
+    pub fn essential_owners(&self, block_id: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.owners_keeping_alive(block_id)?)
+    }
+
+    fn owners_keeping_alive<'a>(&'a self, block_id: &'a str) -> Result<Vec<&'a str>, EngineError> {
+        let block = self
+            .memory_blocks
+            .get(block_id)
+            .ok_or_else(|| EngineError(format!("Memory block not found: {}", block_id)))?;
+        let mut ids: Vec<&str> = if block.freed_at.is_some() {
+            Vec::new()
+        } else {
+            self.pointers_targeting(block_id).filter(|p| p.kind.owns()).map(|p| p.id.as_str()).collect()
+        };
+        ids.sort();
+        Ok(ids)
+    }
+
+    // Proyección 3D a 2D mediante la matriz activa (isométrica por defecto)
+    fn project(&self, x: f64, y: f64, z: f64) -> (f64, f64) {
+        apply_projection(&self.projection, x, y, z)
+    }
+
+    // Rectángulo proyectado de un bloque: (x, y, ancho, alto)
+    fn block_rect(&self, block: &MemoryBlock3D) -> (f64, f64, f64, f64) {
+        projected_rect(&self.projection, block)
+    }
+
+    // Matriz de proyección a medida: 8 coeficientes (2x4) o 6 (2x3, sin traslación)
+    #[wasm_bindgen]
+    pub fn set_projection_matrix(&mut self, m: JsValue) -> Result<(), JsValue> {
+        let coefficients: Vec<f64> = serde_wasm_bindgen::from_value(m)?;
+        self.projection = projection_from_coefficients(&coefficients)?;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Proyecciones predefinidas: "isometric", "orthographic" o "cabinet"
+    #[wasm_bindgen]
+    pub fn set_projection_preset(&mut self, name: &str) -> Result<(), JsValue> {
+        self.projection = match name {
+            "isometric" => ISOMETRIC_PROJECTION,
+            "orthographic" => ORTHOGRAPHIC_PROJECTION,
+            "cabinet" => CABINET_PROJECTION,
+            other => return Err(JsValue::from_str(&format!("Unknown projection preset: {}", other))),
+        };
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_projection_matrix(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.projection)
+    }
+
+    fn render_memory_block(&self, context: &CanvasRenderingContext2d, block: &MemoryBlock3D, projection: &ProjectionMatrix) {
+        let (iso_x, iso_y, width, height) = projected_rect(projection, block);
+
+        if let Some(dump) = self.hex_dumps.get(&block.id) {
+            self.render_hex_grid(context, dump, block.address, iso_x, iso_y, &block.color);
+            return;
+        }
+
+        // Los bloques liberados se dibujan atenuados y con borde discontinuo, igual que los
+        // parámetros de tipo sin instanciar
+        let freed = block.freed_at.is_some();
+        let dashed = freed || block.type_param.is_some();
+        if freed {
+            context.set_global_alpha(context.global_alpha() * 0.5);
+        }
+        if dashed {
+            context.set_line_dash(&js_sys::Array::of2(&6.0.into(), &4.0.into())).unwrap();
+        }
+
+        if self.render_3d_boxes && block.depth > 0.0 {
+            self.render_box_faces(context, block, projection);
+        }
+
+        // Dibujar bloque como un rectángulo con perspectiva (gris si se movió su valor)
+        context.set_fill_style_str(if block.moved_from { MOVED_FROM_COLOR } else { &block.color });
+        context.fill_rect(iso_x, iso_y, width, height);
+
+        // Dibujar borde (distinto para la memoria de solo lectura)
+        let (border_color, border_width) = block_border(block);
+        context.set_stroke_style_str(border_color);
+        self.set_stroke_width(context, border_width);
+        context.stroke_rect(iso_x, iso_y, width, height);
+
+        if dashed {
+            context.set_line_dash(&js_sys::Array::new()).unwrap();
+        }
+
+        if let Some(ref type_name) = block.type_name {
+            self.draw_badge(context, type_name, iso_x, iso_y - 18.0);
+        }
+
+        if let Some(axis) = self.time_axis {
+            self.render_lifetime_bar(context, block, &axis, iso_y + height + 8.0);
+        }
+
+        if let Some(flash) = self.block_flashes.get(&block.id) {
+            context.set_fill_style_str(&format!("rgba(255, 255, 255, {:.2})", flash.remaining() * 0.6));
+            context.fill_rect(iso_x, iso_y, width, height);
+        }
+
+        if self.show_refcount_history {
+            if let Some(history) = self.refcount_history.get(&block.id) {
+                self.render_sparkline(context, history, iso_x + width + 6.0, iso_y, height / 2.0);
+            }
+        }
+
+        if self.is_highlighted(&block.id) {
+            context.set_stroke_style_str("#FFD700");
+            self.set_stroke_width(context, 4.0);
+            context.stroke_rect(iso_x - 3.0, iso_y - 3.0, width + 6.0, height + 6.0);
+        }
+
+        if block.locked {
+            self.draw_lock_badge(context, iso_x + width - 8.0, iso_y - 8.0);
+        }
+
+        if let Some(&slots) = self.array_bounds.get(&block.id) {
+            self.render_danger_zone(context, (iso_x, iso_y, width, height), slots);
+        }
+
+        // Bloques marcados como vivos por el recolector
+        if self.gc_marked.as_ref().is_some_and(|marked| marked.contains(&block.id)) {
+            context.set_stroke_style_str("#2ECC71");
+            self.set_stroke_width(context, 2.0);
+            context.stroke_rect(iso_x - 2.0, iso_y - 2.0, width + 4.0, height + 4.0);
+        }
+
+        if self.show_node_depths {
+            if let Some(depth) = self.node_depths.get(&block.id) {
+                self.draw_badge(context, &format!("d{}", depth), iso_x + 2.0, iso_y + height - 18.0);
+            }
+        }
+
+        // Dibujar valor si existe
+        if let Some((text, font)) = block_label(block) {
+            context.set_fill_style_str(block_label_color(block));
+            context.set_font(font);
+            context.fill_text(text, iso_x + BLOCK_LABEL_OFFSET.0, iso_y + BLOCK_LABEL_OFFSET.1).unwrap();
+        }
+    }
+
+    // Resuelve un parámetro de tipo: el bloque pasa a tener el tipo concreto y destella al cambiar
+    #[wasm_bindgen]
+    pub fn instantiate_type_param(&mut self, block_id: &str, concrete_type: &str) -> Result<(), JsValue> {
+        self.ensure_unlocked(block_id)?;
+        let block = self
+            .memory_blocks
+            .get_mut(block_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))?;
+        if block.type_param.take().is_none() {
+            return Err(JsValue::from_str(&format!("Block is not a type parameter: {}", block_id)));
+        }
+        block.type_name = Some(concrete_type.to_string());
+        self.block_flashes.insert(block_id.to_string(), Effect::new(TYPE_INSTANTIATION_FLASH_MS));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Huella en pantalla de un objeto: su rectángulo proyectado más el texto de la etiqueta
+    #[wasm_bindgen]
+    pub fn object_bounds(&self, id: &str) -> Result<JsValue, JsValue> {
+        let bounds = self.object_footprint(id, |text, font| {
+            let (_, context) = create_offscreen_canvas(1, 1)?;
+            measure_text_extent(&context, text, font)
+        })?;
+        to_js(&bounds)
+    }
+
+    fn object_footprint(
+        &self,
+        id: &str,
+        measure: impl FnOnce(&str, &str) -> Result<TextExtent, JsValue>,
+    ) -> Result<ScreenRect, JsValue> {
+        let (mut x0, mut y0, width, height) =
+            self.object_rect(id).ok_or_else(|| JsValue::from_str(&format!("Object not found: {}", id)))?;
+        let (mut x1, mut y1) = (x0 + width, y0 + height);
+
+        let mut cover = |(x, y): (f64, f64)| {
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+        };
+        if let Some(block) = self.memory_blocks.get(id).filter(|block| self.render_3d_boxes && block.depth > 0.0) {
+            box_faces(block, &self.projection).iter().flat_map(|(corners, _)| corners).for_each(|&corner| cover(corner));
+        }
+        if let Some(pointer) = self.pointers.get(id) {
+            self.pointer_outline(pointer).into_iter().for_each(cover);
+        }
+
+        if let Some((text, font)) = self.memory_blocks.get(id).and_then(block_label) {
+            let extent = measure(text, font)?;
+            let (left, baseline) = (x0 + BLOCK_LABEL_OFFSET.0, y0 + BLOCK_LABEL_OFFSET.1);
+            x0 = x0.min(left - extent.left);
+            x1 = x1.max(left + extent.right);
+            y0 = y0.min(baseline - extent.ascent);
+            y1 = y1.max(baseline + extent.descent);
+        }
+
+        let zoom = self.camera.zoom;
+        Ok(ScreenRect {
+            x: x0 * zoom + self.camera.pan_x,
+            y: y0 * zoom + self.camera.pan_y,
+            width: (x1 - x0) * zoom,
+            height: (y1 - y0) * zoom,
+        })
+    }
+
+    // Puntos que delimitan el trazo de un puntero tal y como se dibuja: extremos, punto de control y punta
+    fn pointer_outline(&self, pointer: &Pointer3D) -> Vec<(f64, f64)> {
+        let start = self.project(pointer.start_x, pointer.start_y, pointer.start_z);
+        let end = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+        if pointer.pending {
+            let (tip, mark) = pending_terminus(start, end);
+            return vec![start, tip, mark];
+        }
+        let mut points = vec![start, end];
+        if pointer.kind != PointerKind::Reference {
+            let control = self.curve_control_point(&self.projection, pointer, start, end);
+            let (from_x, from_y) = control.unwrap_or(start);
+            points.extend(control);
+            points.extend(arrow_head_points(end.0, end.1, from_x, from_y));
+        }
+        points
+    }
+
+    fn render_time_axis(&self, context: &CanvasRenderingContext2d, axis: &TimeAxis) {
+        let mut times: Vec<f64> = self.memory_blocks.values().filter_map(|b| b.allocated_at).collect();
+        times.sort_by(f64::total_cmp);
+        let end_x = times
+            .last()
+            .map(|t| TIME_AXIS_LEFT + (t - axis.origin_ms) * axis.pixels_per_ms)
+            .unwrap_or(TIME_AXIS_LEFT)
+            + 60.0;
+
+        context.set_stroke_style_str("#888888");
+        context.set_fill_style_str("#888888");
+        self.set_stroke_width(context, 1.0);
+        context.set_font("11px Arial");
+        context.begin_path();
+        context.move_to(TIME_AXIS_LEFT, axis.axis_y);
+        context.line_to(end_x, axis.axis_y);
+        context.stroke();
+
+        // Una marca por cada instante de reserva
+        for time in times {
+            let x = TIME_AXIS_LEFT + (time - axis.origin_ms) * axis.pixels_per_ms;
+            context.begin_path();
+            context.move_to(x, axis.axis_y - 4.0);
+            context.line_to(x, axis.axis_y + 4.0);
+            context.stroke();
+            context
+                .fill_text(&format!("{}ms", (time - axis.origin_ms).round()), x - 10.0, axis.axis_y + 16.0)
+                .unwrap();
+        }
+    }
+
+    // Caras superior/inferior y lateral visibles de la caja extruida (la frontal se dibuja aparte)
+    fn render_box_faces(&self, context: &CanvasRenderingContext2d, block: &MemoryBlock3D, projection: &ProjectionMatrix) {
+        context.set_stroke_style_str("white");
+        self.set_stroke_width(context, 1.0);
+        for (corners, color) in box_faces(block, projection) {
+            context.set_fill_style_str(&color);
+            context.begin_path();
+            context.move_to(corners[0].0, corners[0].1);
+            for (x, y) in &corners[1..] {
+                context.line_to(*x, *y);
+            }
+            context.close_path();
+            context.fill();
+            context.stroke();
+        }
+    }
+
+    // Tramo horizontal (inicio, fin) de la vida de un bloque; si sigue vivo llega hasta ahora
+    fn lifetime_span(&self, block: &MemoryBlock3D, axis: &TimeAxis) -> Option<(f64, f64)> {
+        let allocated_at = block.allocated_at?;
+        let to_x = |time: f64| TIME_AXIS_LEFT + (time - axis.origin_ms) * axis.pixels_per_ms;
+        Some((to_x(allocated_at), to_x(block.freed_at.unwrap_or_else(|| self.now().max(allocated_at)))))
+    }
+
+    // Barra de vida del bloque: punto en la reserva y aspa en la liberación
+    fn render_lifetime_bar(&self, context: &CanvasRenderingContext2d, block: &MemoryBlock3D, axis: &TimeAxis, y: f64) {
+        let Some((start_x, end_x)) = self.lifetime_span(block, axis) else {
+            return;
+        };
+
+        context.set_stroke_style_str(&block.color);
+        context.set_fill_style_str(&block.color);
+        self.set_stroke_width(context, 4.0);
+        context.begin_path();
+        context.move_to(start_x, y);
+        context.line_to(end_x, y);
+        context.stroke();
+
+        context.begin_path();
+        context.arc(start_x, y, 4.0, 0.0, f64::consts::PI * 2.0).unwrap();
+        context.fill();
+
+        if let Some(x) = self.lifetime_end_marker(block, axis) {
+            context.set_stroke_style_str("#E74C3C");
+            self.set_stroke_width(context, 2.0);
+            context.begin_path();
+            context.move_to(x - 5.0, y - 5.0);
+            context.line_to(x + 5.0, y + 5.0);
+            context.move_to(x + 5.0, y - 5.0);
+            context.line_to(x - 5.0, y + 5.0);
+            context.stroke();
+        }
+    }
+
+    // Posición del aspa de liberación; los bloques vivos no tienen marcador de fin
+    fn lifetime_end_marker(&self, block: &MemoryBlock3D, axis: &TimeAxis) -> Option<f64> {
+        block.freed_at?;
+        self.lifetime_span(block, axis).map(|(_, end_x)| end_x)
+    }
+
+    // Zona roja translúcida justo después del último slot válido del array
+    fn render_danger_zone(&self, context: &CanvasRenderingContext2d, rect: (f64, f64, f64, f64), slots: usize) {
+        let (zone_x, y, zone_width, height) = danger_zone_rect(rect, slots);
+
+        context.set_fill_style_str("rgba(231, 76, 60, 0.3)");
+        context.fill_rect(zone_x, y, zone_width, height);
+        context.set_stroke_style_str("#E74C3C");
+        self.set_stroke_width(context, 1.5);
+        context.set_line_dash(&js_sys::Array::of2(&4.0.into(), &3.0.into())).unwrap();
+        context.stroke_rect(zone_x, y, zone_width, height);
+        context.set_line_dash(&js_sys::Array::new()).unwrap();
+
+        context.set_fill_style_str("#E74C3C");
+        context.set_font("bold 11px Arial");
+        context.fill_text("OOB", zone_x + 4.0, y + height / 2.0 + 4.0).unwrap();
+    }
+
+    // Medidor vertical de profundidad de pila frente al máximo configurado
+    fn render_depth_meter(&self, context: &CanvasRenderingContext2d, x: f64, y: f64, height: f64) {
+        let depth = self.stack_depth();
+        let ratio = (depth as f64 / self.max_stack_depth as f64).min(1.0);
+        let width = 14.0;
+
+        context.set_fill_style_str("rgba(255, 255, 255, 0.15)");
+        context.fill_rect(x, y, width, height);
+        context.set_fill_style_str(if depth > self.max_stack_depth { "#E74C3C" } else { "#2ECC71" });
+        context.fill_rect(x, y + height * (1.0 - ratio), width, height * ratio);
+        context.set_stroke_style_str("white");
+        self.set_stroke_width(context, 1.0);
+        context.stroke_rect(x, y, width, height);
+
+        context.set_fill_style_str("white");
+        context.set_font("11px Arial");
+        context
+            .fill_text(&format!("{}/{}", depth, self.max_stack_depth), x - 8.0, y + height + 14.0)
+            .unwrap();
+    }
+
+    // Marcas (x de pantalla, dirección) de la regla en múltiplos exactos del paso en coordenadas de mundo
+    fn ruler_ticks(&self, ruler: &AddressRuler, width: f64) -> Vec<(f64, u64)> {
+        let world_step = RULER_TICK_SPACING / self.camera.zoom;
+        let (world_left, _) = self.unproject_screen(0.0, 0.0);
+        let mut world_x = (world_left / world_step).ceil() * world_step;
+        let mut ticks = Vec::new();
+
+        loop {
+            let screen_x = world_x * self.camera.zoom + self.camera.pan_x;
+            if screen_x > width {
+                break;
+            }
+            let offset = (world_x * ruler.bytes_per_pixel).round() as i64;
+            ticks.push((screen_x, ruler.base_address.wrapping_add_signed(offset)));
+            world_x += world_step;
+        }
+        ticks
+    }
+
+    fn render_address_ruler(&self, context: &CanvasRenderingContext2d, ruler: &AddressRuler, width: f64) {
+        context.set_fill_style_str("rgba(0, 0, 0, 0.6)");
+        context.fill_rect(0.0, 0.0, width, 22.0);
+        context.set_stroke_style_str("#BBBBBB");
+        context.set_fill_style_str("#BBBBBB");
+        self.set_stroke_width(context, 1.0);
+        context.set_font("10px monospace");
+
+        for (screen_x, address) in self.ruler_ticks(ruler, width) {
+            context.begin_path();
+            context.move_to(screen_x, 14.0);
+            context.line_to(screen_x, 22.0);
+            context.stroke();
+            context.fill_text(&format!("0x{:X}", address), screen_x + 2.0, 11.0).unwrap();
+        }
+    }
+
+    fn render_presenter_pointer(&self, context: &CanvasRenderingContext2d, x: f64, y: f64) {
+        // Estela que se desvanece desde la posición más antigua
+        for (i, (trail_x, trail_y)) in self.presenter_trail.iter().enumerate() {
+            let age = (i + 1) as f64 / (self.presenter_trail.len() + 1) as f64;
+            context.set_fill_style_str(&format!("rgba(255, 40, 40, {:.2})", age * 0.5));
+            context.begin_path();
+            context.arc(*trail_x, *trail_y, PRESENTER_DOT_RADIUS * age, 0.0, f64::consts::PI * 2.0).unwrap();
+            context.fill();
+        }
+
+        context.save();
+        context.set_shadow_color("rgba(255, 40, 40, 0.9)");
+        context.set_shadow_blur(16.0);
+        context.set_fill_style_str("#FF2828");
+        context.begin_path();
+        context.arc(x, y, PRESENTER_DOT_RADIUS, 0.0, f64::consts::PI * 2.0).unwrap();
+        context.fill();
+        context.restore();
+    }
+
+    // Texto de la lectura de coordenadas del cursor, si está activa
+    fn cursor_readout_text(&self) -> Option<String> {
+        let (screen_x, screen_y) = self.cursor_readout?;
+        let (world_x, world_y) = self.unproject_screen(screen_x, screen_y);
+        Some(format!(
+            "screen ({:.1}, {:.1})  world ({:.1}, {:.1})",
+            screen_x, screen_y, world_x, world_y
+        ))
+    }
+
+    // Minigráfica de los valores de ref_count junto al bloque
+    fn render_sparkline(&self, context: &CanvasRenderingContext2d, history: &VecDeque<(f64, u32)>, x: f64, y: f64, height: f64) {
+        if history.len() < 2 {
+            return;
+        }
+        let max = history.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1) as f64;
+        let step = SPARKLINE_WIDTH / (history.len() - 1) as f64;
+
+        context.set_stroke_style_str("#00BFFF");
+        self.set_stroke_width(context, 1.5);
+        context.begin_path();
+        for (i, (_, value)) in history.iter().enumerate() {
+            let point_x = x + i as f64 * step;
+            let point_y = y + height - (*value as f64 / max) * height;
+            if i == 0 {
+                context.move_to(point_x, point_y);
+            } else {
+                context.line_to(point_x, point_y);
+            }
+        }
+        context.stroke();
+    }
+
+    fn render_hex_grid(&self, context: &CanvasRenderingContext2d, dump: &HexDump, base: Option<u64>, x: f64, y: f64, color: &str) {
+        context.set_font("12px monospace");
+        self.set_stroke_width(context, 1.0);
+
+        // El canal se ensancha para que quepa la etiqueta más larga (la de la última fila)
+        let rows = dump.rows(base);
+        let label_width = rows.last().map_or(0, |(label, _)| label.len());
+        let gutter = HEX_GUTTER_WIDTH.max(label_width as f64 * 7.5 + 22.0);
+
+        for (row, (label, chunk)) in rows.iter().enumerate() {
+            let row_y = y + row as f64 * HEX_CELL_HEIGHT;
+
+            // Columna de direcciones (dirección real de la fila o, sin ella, su desplazamiento)
+            context.set_fill_style_str("#888888");
+            context.fill_text(label, x, row_y + 14.0).unwrap();
+
+            for (col, byte) in chunk.iter().enumerate() {
+                let cell_x = x + gutter + col as f64 * HEX_CELL_WIDTH;
+
+                context.set_fill_style_str(color);
+                context.fill_rect(cell_x, row_y, HEX_CELL_WIDTH, HEX_CELL_HEIGHT);
+                context.set_stroke_style_str("white");
+                context.stroke_rect(cell_x, row_y, HEX_CELL_WIDTH, HEX_CELL_HEIGHT);
+
+                context.set_fill_style_str("white");
+                context.fill_text(&format!("{:02X}", byte), cell_x + 6.0, row_y + 14.0).unwrap();
+            }
+        }
+    }
+
+    fn render_pointer(&self, context: &CanvasRenderingContext2d, pointer: &Pointer3D, projection: &ProjectionMatrix) {
+        let (start_iso_x, start_iso_y) = apply_projection(projection, pointer.start_x, pointer.start_y, pointer.start_z);
+        let (end_iso_x, end_iso_y) = apply_projection(projection, pointer.end_x, pointer.end_y, pointer.end_z);
+
+        if pointer.pending {
+            self.render_pending_pointer(context, pointer, (start_iso_x, start_iso_y), (end_iso_x, end_iso_y));
+        } else if pointer.kind == PointerKind::Reference {
+            self.render_reference_binding(context, pointer, (start_iso_x, start_iso_y), (end_iso_x, end_iso_y));
+        } else {
+            // Dibujar línea del puntero (curva si tiene punto de control)
+            let control = self.curve_control_point(projection, pointer, (start_iso_x, start_iso_y), (end_iso_x, end_iso_y));
+            let (color, head_color) = self.pointer_colors(pointer);
+            context.set_stroke_style_str(&color);
+            self.set_stroke_width(context, self.line_thickness(pointer));
+            context.begin_path();
+            context.move_to(start_iso_x, start_iso_y);
+            let (from_x, from_y) = match control {
+                Some((cx, cy)) => {
+                    context.quadratic_curve_to(cx, cy, end_iso_x, end_iso_y);
+                    (cx, cy)
+                }
+                None => {
+                    context.line_to(end_iso_x, end_iso_y);
+                    (start_iso_x, start_iso_y)
+                }
+            };
+            context.stroke();
+
+            // Dibujar cabeza de flecha, tangente a la curva en el extremo
+            context.set_stroke_style_str(&head_color);
+            self.draw_arrow_head(context, end_iso_x, end_iso_y, from_x, from_y);
+
+            // Peso de la arista en el punto medio de la línea (t = 0.5 en la curva)
+            if let Some(weight) = pointer.weight {
+                let (mid_x, mid_y) = curve_midpoint((start_iso_x, start_iso_y), control, (end_iso_x, end_iso_y));
+                let label = weight.to_string();
+                self.draw_badge(context, &label, mid_x - (label.len() as f64 * 7.0 + 8.0) / 2.0, mid_y - 8.0);
+            }
+        }
+
+        if pointer.locked {
+            self.draw_lock_badge(context, start_iso_x - 8.0, start_iso_y - 8.0);
+        }
+
+        if self.type_flow {
+            self.render_type_flow(context, pointer, (start_iso_x, start_iso_y), (end_iso_x, end_iso_y));
+        }
+
+        if let Some(tag) = pointer.tag_bits {
+            let width = tag_width_of(pointer).unwrap_or(1) as usize;
+            self.draw_badge(context, &format!("0b{:0width$b}", tag, width = width), start_iso_x, start_iso_y - 18.0);
+        }
+
+        // Salto en bytes mientras dura una animación de aritmética de punteros
+        if let Some(delta_bytes) = self.stride_indicators.get(&pointer.id) {
+            self.draw_badge(context, &format!("{:+}", delta_bytes), end_iso_x + 6.0, end_iso_y - 22.0);
+        }
+
+        if self.show_self_references && self.self_reference_of(pointer).is_some() {
+            self.draw_caution_marker(context, start_iso_x + 6.0, start_iso_y - 26.0);
+        }
+
+        if self.show_storage_classes {
+            if let Some(crossing) = self.storage_crossing(pointer) {
+                let (mid_x, mid_y) = ((start_iso_x + end_iso_x) / 2.0, (start_iso_y + end_iso_y) / 2.0);
+                self.draw_badge(context, &crossing.crossing, mid_x + 6.0, mid_y - 22.0);
+            }
+        }
+    }
+
+    // Colores de la línea y de la punta: el de cruce de regiones, el del destino (con la punta más
+    // oscura) si match_target_color está activo, o el propio del puntero
+    fn pointer_colors(&self, pointer: &Pointer3D) -> (String, String) {
+        if let Some(ref color) = self.cross_region_color {
+            if self.region_crossing(pointer).is_some() {
+                return (color.clone(), color.clone());
+            }
+        }
+        if !self.match_target_color {
+            return (pointer.color.clone(), pointer.color.clone());
+        }
+
+        let color = if self.is_pointer_null(pointer) {
+            NULL_POINTER_COLOR.to_string()
+        } else if self.is_pointer_dangling(pointer) {
+            DANGLING_POINTER_COLOR.to_string()
+        } else {
+            match self.target_of(pointer).and_then(|id| self.memory_blocks.get(id)) {
+                Some(block) => block.color.clone(),
+                None => pointer.color.clone(),
+            }
+        };
+        let head_color = shade_color(&color, 0.75);
+        (color, head_color)
+    }
+
+    // Con el escalado por peso activo, el grosor es proporcional al peso de la arista
+    fn line_thickness(&self, pointer: &Pointer3D) -> f64 {
+        match pointer.weight {
+            Some(weight) if self.scale_thickness_by_weight => {
+                (weight.abs() * WEIGHT_THICKNESS_SCALE).clamp(MIN_WEIGHTED_THICKNESS, MAX_WEIGHTED_THICKNESS)
+            }
+            _ => pointer.thickness,
+        }
+    }
+
+    // Referencia: doble línea continua (como un "=") entre el alias y su destino, sin punta
+    fn render_reference_binding(&self, context: &CanvasRenderingContext2d, pointer: &Pointer3D, start: (f64, f64), end: (f64, f64)) {
+        context.set_stroke_style_str(&pointer.color);
+        self.set_stroke_width(context, reference_line_width(pointer));
+        context.begin_path();
+        for (from, to) in reference_binding_lines(pointer, start, end) {
+            context.move_to(from.0, from.1);
+            context.line_to(to.0, to.1);
+        }
+        context.stroke();
+    }
+
+    // Puntero declarado sin destino: flecha corta discontinua terminada en interrogación
+    fn render_pending_pointer(&self, context: &CanvasRenderingContext2d, pointer: &Pointer3D, start: (f64, f64), end: (f64, f64)) {
+        let ((tip_x, tip_y), (mark_x, mark_y)) = pending_terminus(start, end);
+
+        context.set_stroke_style_str(&pointer.color);
+        self.set_stroke_width(context, pointer.thickness);
+        context.set_line_dash(&js_sys::Array::of2(&5.0.into(), &4.0.into())).unwrap();
+        context.begin_path();
+        context.move_to(start.0, start.1);
+        context.line_to(tip_x, tip_y);
+        context.stroke();
+        context.set_line_dash(&js_sys::Array::new()).unwrap();
+
+        context.set_fill_style_str(&pointer.color);
+        context.set_font(PENDING_MARK_FONT);
+        context.fill_text("?", mark_x, mark_y).unwrap();
+    }
+
+    // Punto de control manual o, con la curvatura por indirección activa, desplazado en
+    // perpendicular proporcionalmente a la profundidad
+    fn curve_control_point(
+        &self,
+        projection: &ProjectionMatrix,
+        pointer: &Pointer3D,
+        start: (f64, f64),
+        end: (f64, f64),
+    ) -> Option<(f64, f64)> {
+        if let Some((x, y, z)) = pointer.control_point {
+            return Some(apply_projection(projection, x, y, z));
+        }
+        if !self.indirection_bend {
+            return None;
+        }
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return None;
+        }
+
+        let offset = self.indirection_depth(pointer) as f64 * INDIRECTION_BEND_PER_LEVEL;
+        let (mid_x, mid_y) = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+        Some((mid_x - dy / length * offset, mid_y + dx / length * offset))
+    }
+
+    fn render_type_flow(&self, context: &CanvasRenderingContext2d, pointer: &Pointer3D, start: (f64, f64), end: (f64, f64)) {
+        let source_type = pointer.type_name.as_deref();
+        let target_type = pointer.target_type_name.as_deref();
+
+        if let Some(source_type) = source_type {
+            self.draw_badge(context, source_type, start.0 + 6.0, start.1 + 6.0);
+        }
+        if let Some(target_type) = target_type {
+            self.draw_badge(context, target_type, end.0 + 6.0, end.1 + 6.0);
+        }
+
+        // Indicador de conversión implícita cuando los tipos difieren
+        if type_conversion(pointer).is_some() {
+            let (mid_x, mid_y) = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+            context.set_fill_style_str("#E67E22");
+            context.begin_path();
+            context.arc(mid_x, mid_y, 9.0, 0.0, f64::consts::PI * 2.0).unwrap();
+            context.fill();
+            context.set_fill_style_str("white");
+            context.set_font("bold 12px Arial");
+            context.fill_text("⇄", mid_x - 6.0, mid_y + 4.0).unwrap();
+        }
+    }
+
+    // Triángulo amarillo de precaución con un signo de exclamación
+    fn draw_caution_marker(&self, context: &CanvasRenderingContext2d, x: f64, y: f64) {
+        context.set_fill_style_str("#F1C40F");
+        context.begin_path();
+        context.move_to(x + 8.0, y);
+        context.line_to(x + 16.0, y + 14.0);
+        context.line_to(x, y + 14.0);
+        context.close_path();
+        context.fill();
+        context.set_fill_style_str("black");
+        context.set_font("bold 11px Arial");
+        context.fill_text("!", x + 6.0, y + 12.5).unwrap();
+    }
+
+    // Candado pequeño: arco superior y cuerpo rectangular
+    fn draw_lock_badge(&self, context: &CanvasRenderingContext2d, x: f64, y: f64) {
+        context.set_stroke_style_str("#F1C40F");
+        context.set_fill_style_str("#F1C40F");
+        self.set_stroke_width(context, 1.5);
+        context.begin_path();
+        context.arc(x + 5.0, y + 5.0, 3.5, f64::consts::PI, 0.0).unwrap();
+        context.stroke();
+        context.fill_rect(x, y + 5.0, 10.0, 8.0);
+    }
+
+    // Pequeña etiqueta con fondo oscuro
+    fn draw_badge(&self, context: &CanvasRenderingContext2d, text: &str, x: f64, y: f64) {
+        context.set_font("11px monospace");
+        let width = text.len() as f64 * 7.0 + 8.0;
+
+        context.set_fill_style_str("rgba(0, 0, 0, 0.75)");
+        context.fill_rect(x, y, width, 16.0);
+        context.set_fill_style_str("white");
+        context.fill_text(text, x + 4.0, y + 12.0).unwrap();
+    }
+
+    fn render_selection(&self, context: &CanvasRenderingContext2d, (x, y, w, h): (f64, f64, f64, f64)) {
+        context.set_stroke_style_str("#00E5FF");
+        self.set_stroke_width(context, 2.0);
+        context.set_line_dash(&js_sys::Array::of2(&4.0.into(), &3.0.into())).unwrap();
+        context.stroke_rect(x - 5.0, y - 5.0, w + 10.0, h + 10.0);
+        context.set_line_dash(&js_sys::Array::new()).unwrap();
+    }
+
+    // Insignias numeradas con la posición de cada objeto en el orden de foco
+    fn render_focus_order(&self, context: &CanvasRenderingContext2d, projection: &ProjectionMatrix) {
+        for (index, id) in self.focus_ids().iter().enumerate() {
+            if let Some((x, y, _, _)) = self.object_rect_in(projection, id) {
+                self.draw_badge(context, &(index + 1).to_string(), x - 10.0, y - 10.0);
+            }
+        }
+    }
+
+    // Estallido rojo en el punto donde falló la desreferencia
+    fn render_crash_burst(&self, context: &CanvasRenderingContext2d, x: f64, y: f64, intensity: f64) {
+        let radius = 10.0 + (1.0 - intensity) * 20.0;
+
+        context.set_stroke_style_str(&format!("rgba(231, 76, 60, {:.2})", intensity));
+        self.set_stroke_width(context, 3.0);
+        context.begin_path();
+        for i in 0..8 {
+            let angle = i as f64 * f64::consts::PI / 4.0;
+            context.move_to(x + angle.cos() * radius * 0.4, y + angle.sin() * radius * 0.4);
+            context.line_to(x + angle.cos() * radius, y + angle.sin() * radius);
+        }
+        context.stroke();
+    }
+
+    fn render_comparison(&self, context: &CanvasRenderingContext2d, comparison: &PointerComparison, projection: &ProjectionMatrix) {
+        let (Some(a), Some(b)) = (self.pointers.get(&comparison.id_a), self.pointers.get(&comparison.id_b)) else {
+            return;
+        };
+        let (ax, ay) = apply_projection(projection, a.start_x, a.start_y, a.start_z);
+        let (bx, by) = apply_projection(projection, b.start_x, b.start_y, b.start_z);
+        let color = self.comparison_color(comparison);
+
+        context.set_stroke_style_str(color);
+        context.set_fill_style_str(color);
+        self.set_stroke_width(context, 2.0);
+        context.begin_path();
+        context.move_to(ax, ay);
+        context.line_to(bx, by);
+        context.stroke();
+
+        // Marcadores en el origen de cada puntero
+        for (x, y) in [(ax, ay), (bx, by)] {
+            context.begin_path();
+            context.arc(x, y, 4.0, 0.0, f64::consts::PI * 2.0).unwrap();
+            context.fill();
+        }
+
+        context.set_font("bold 14px Arial");
+        context.fill_text(comparison.symbol(), (ax + bx) / 2.0 + 6.0, (ay + by) / 2.0 - 6.0).unwrap();
+    }
+
+    fn draw_arrow_head(&self, context: &CanvasRenderingContext2d, x: f64, y: f64, from_x: f64, from_y: f64) {
+        let [(x1, y1), (x2, y2)] = arrow_head_points(x, y, from_x, from_y);
+
+        context.begin_path();
+        context.move_to(x, y);
+        context.line_to(x1, y1);
+        context.move_to(x, y);
+        context.line_to(x2, y2);
+        context.stroke();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_pointer_count(&self) -> usize {
+        self.pointers.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_memory_block_count(&self) -> usize {
+        self.memory_blocks.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_animation_speed(&mut self, speed: f64) {
+        self.speed_ramp = None;
+        self.animation_speed = clamp_animation_speed(speed);
+    }
+
+    // Cambia la velocidad de forma progresiva en lugar de instantánea
+    #[wasm_bindgen]
+    pub fn ramp_animation_speed(&mut self, target: f64, duration_ms: f64) {
+        if duration_ms <= 0.0 || duration_ms.is_nan() {
+            self.set_animation_speed(target);
+            return;
+        }
+        self.speed_ramp = Some(SpeedRamp {
+            from: self.animation_speed,
+            to: clamp_animation_speed(target),
+            elapsed_ms: 0.0,
+            duration_ms,
+        });
+    }
+
+    #[wasm_bindgen]
+    pub fn set_speed_ramp_easing(&mut self, easing: Easing) {
+        self.speed_ramp_easing = easing;
+    }
+
+    // Velocidad efectiva actual (incluida la rampa en curso)
+    #[wasm_bindgen]
+    pub fn get_animation_speed(&self) -> f64 {
+        self.animation_speed
+    }
+
+    // La rampa avanza en tiempo real, sin escalar por la propia velocidad
+    fn advance_speed_ramp(&mut self, delta_ms: f64) {
+        let Some(ref mut ramp) = self.speed_ramp else {
+            return;
+        };
+        ramp.elapsed_ms += delta_ms;
+
+        let t = self.speed_ramp_easing.apply(ramp.elapsed_ms / ramp.duration_ms);
+        self.animation_speed = ramp.from + (ramp.to - ramp.from) * t;
+        if ramp.elapsed_ms >= ramp.duration_ms {
+            self.animation_speed = ramp.to;
+            self.speed_ramp = None;
+        }
+    }
+
+    // Agrupa punteros bajo un nombre para operar sobre todos a la vez
+    #[wasm_bindgen]
+    pub fn create_pointer_group(&mut self, name: &str, ids: JsValue) -> Result<(), JsValue> {
+        self.group_pointers(name, serde_wasm_bindgen::from_value(ids)?)
+    }
+
+    fn group_pointers(&mut self, name: &str, ids: Vec<String>) -> Result<(), JsValue> {
+        if let Some(missing) = ids.iter().find(|id| !self.pointers.contains_key(id.as_str())) {
+            return Err(JsValue::from_str(&format!("Pointer not found: {}", missing)));
+        }
+
+        self.pointer_groups.insert(name.to_string(), ids);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_group_visible(&mut self, name: &str, visible: bool) -> Result<(), JsValue> {
+        for id in self.group_members(name)? {
+            if let Some(pointer) = self.pointers.get_mut(&id) {
+                pointer.visible = visible;
+            }
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_group_color(&mut self, name: &str, color: &str) -> Result<(), JsValue> {
+        for id in self.group_members(name)? {
+            if let Some(pointer) = self.pointers.get_mut(&id) {
+                pointer.color = color.to_string();
+            }
+        }
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Elimina el grupo junto con todos sus punteros
+    #[wasm_bindgen]
+    pub fn remove_group(&mut self, name: &str) -> Result<(), JsValue> {
+        let members = self.group_members(name)?;
+        for id in &members {
+            self.ensure_unlocked(id)?;
+        }
+        for id in &members {
+            self.detach_pointer(id);
+        }
+        self.pointer_groups.remove(name);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    fn group_members(&self, name: &str) -> Result<Vec<String>, JsValue> {
+        self.pointer_groups
+            .get(name)
+            .cloned()
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer group not found: {}", name)))
+    }
+
+    #[wasm_bindgen]
+    pub fn increment_ref_count(&mut self, block_id: &str) -> Result<u32, JsValue> {
+        self.change_ref_count(block_id, |count| count.saturating_add(1))
+    }
+
+    #[wasm_bindgen]
+    pub fn decrement_ref_count(&mut self, block_id: &str) -> Result<u32, JsValue> {
+        self.change_ref_count(block_id, |count| count.saturating_sub(1))
+    }
+
+    // Valores sucesivos de ref_count del bloque, del más antiguo al más reciente
+    #[wasm_bindgen]
+    pub fn get_refcount_history(&self, block_id: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.refcount_values(block_id))
+    }
+
+    fn refcount_values(&self, block_id: &str) -> Vec<u32> {
+        self.refcount_history
+            .get(block_id)
+            .map(|values| values.iter().map(|(_, count)| *count).collect())
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen]
+    pub fn show_refcount_history(&mut self, enabled: bool) {
+        self.show_refcount_history = enabled;
+        self.mark_dirty();
+    }
+
+    fn change_ref_count(&mut self, block_id: &str, change: impl Fn(u32) -> u32) -> Result<u32, JsValue> {
+        let block = self
+            .memory_blocks
+            .get_mut(block_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))?;
+
+        let count = change(block.ref_count.unwrap_or(0));
+        block.ref_count = Some(count);
+
+        let now = self.now();
+        let history = self.refcount_history.entry(block_id.to_string()).or_default();
+        history.push_back((now, count));
+        if history.len() > REFCOUNT_HISTORY_LIMIT {
+            history.pop_front();
+        }
+        self.enforce_memory_budget();
+        self.mark_dirty();
+        Ok(count)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_tag_bits(&mut self, pointer_id: &str, bits: Option<u8>, width: Option<u8>) -> Result<(), JsValue> {
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        if let Some(width) = width.filter(|width| *width > 8) {
+            return Err(JsValue::from_str(&format!("Tag width must be at most 8 bits: {}", width)));
+        }
+
+        // El ancho no se puede deducir del valor: 0b01 en un campo de dos bits sigue ocupando dos
+        if let Some(tag) = bits {
+            let resolved = resolve_tag_width(width, pointer.stride_bytes)
+                .ok_or_else(|| JsValue::from_str(&format!("Tag width unknown for pointer: {}", pointer_id)))?;
+            if u64::from(tag) > tag_mask(resolved) {
+                return Err(JsValue::from_str(&format!("Tag 0b{:b} does not fit in {} bits", tag, resolved)));
+            }
+        }
+        pointer.tag_bits = bits;
+        pointer.tag_width = width;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Apunta el puntero a un bloque (terminando en su ancla) y deja de estar pendiente
+    #[wasm_bindgen]
+    pub fn retarget_pointer(&mut self, pointer_id: &str, block_id: &str) -> Result<(), JsValue> {
+        Ok(self.retarget(pointer_id, block_id)?)
+    }
+
+    fn retarget(&mut self, pointer_id: &str, block_id: &str) -> Result<(), EngineError> {
+        self.ensure_unlocked(pointer_id)?;
+        self.ensure_rebindable(pointer_id)?;
+        if self.is_pinned(pointer_id) {
+            return Ok(());
+        }
+        let (end_x, end_y, end_z) = self
+            .memory_blocks
+            .get(block_id)
+            .map(block_anchor)
+            .ok_or_else(|| EngineError(format!("Memory block not found: {}", block_id)))?;
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| EngineError(format!("Pointer not found: {}", pointer_id)))?;
+
+        pointer.target_block_id = Some(block_id.to_string());
+        pointer.end_x = end_x;
+        pointer.end_y = end_y;
+        pointer.end_z = end_z;
+        pointer.pending = false;
+        pointer.is_null = false;
+        let source = self.trace_participant(pointer_id);
+        self.record_trace(&source, Some(block_id), format!("{} = &{}", pointer_id, block_id), false);
+        self.emit_event("pointer_retargeted", pointer_id);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Peso de la arista mostrado sobre la línea (None lo quita)
+    #[wasm_bindgen]
+    pub fn set_weight(&mut self, pointer_id: &str, weight: Option<f64>) -> Result<(), JsValue> {
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        pointer.weight = weight.filter(|w| w.is_finite());
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_thickness_by_weight(&mut self, enabled: bool) {
+        self.scale_thickness_by_weight = enabled;
+        self.mark_dirty();
+    }
+
+    // Curva el puntero a través de un punto de control cuadrático
+    #[wasm_bindgen]
+    pub fn set_control_point(&mut self, pointer_id: &str, x: f64, y: f64, z: f64) -> Result<(), JsValue> {
+        self.ensure_unlocked(pointer_id)?;
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        pointer.control_point = Some((x, y, z));
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_control_point(&mut self, pointer_id: &str) -> Result<(), JsValue> {
+        self.ensure_unlocked(pointer_id)?;
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        pointer.control_point = None;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Dirección de destino con los bits de etiqueta puestos a cero
+    #[wasm_bindgen]
+    pub fn masked_target_address(&self, pointer_id: &str) -> Result<Option<u64>, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+
+        Ok(pointer.target_address.map(|address| match (pointer.tag_bits, tag_width_of(pointer)) {
+            (Some(_), Some(width)) => address & !tag_mask(width),
+            _ => address,
+        }))
+    }
+
+    // Posición libre más cercana a (near_x, near_y) donde cabe un bloque sin solaparse
+    #[wasm_bindgen]
+    pub fn find_free_slot(&self, width: f64, height: f64, near_x: f64, near_y: f64) -> Result<JsValue, JsValue> {
+        let (x, y) = self.free_slot_near(width, height, near_x, near_y);
+        to_js(&Point2D { x, y })
+    }
+
+    fn free_slot_near(&self, width: f64, height: f64, near_x: f64, near_y: f64) -> (f64, f64) {
+        let rects: Vec<_> = self.memory_blocks.values().map(|block| self.block_rect(block)).collect();
+        let gap = FREE_SLOT_GAP;
+
+        // Candidatos: el punto pedido y posiciones pegadas a cada lado de cada bloque
+        let mut candidates = vec![(near_x, near_y)];
+        for &(x, y, w, h) in &rects {
+            let xs = [near_x, x - width - gap, x + w + gap];
+            let ys = [near_y, y - height - gap, y + h + gap];
+            for cx in xs {
+                for cy in ys {
+                    candidates.push((cx, cy));
+                }
+            }
+        }
+
+        let is_free = |&(x, y): &(f64, f64)| {
+            let candidate = (x - gap / 2.0, y - gap / 2.0, width + gap, height + gap);
+            rects.iter().all(|rect| !rects_overlap(candidate, *rect))
+        };
+        let distance = |&(x, y): &(f64, f64)| (x - near_x).powi(2) + (y - near_y).powi(2);
+
+        candidates
+            .into_iter()
+            .filter(is_free)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or_else(|| {
+                // Sin hueco cercano: colocar debajo de todo el contenido
+                let bottom = rects.iter().map(|r| r.1 + r.3).fold(near_y, f64::max);
+                (near_x, bottom + gap)
+            })
+    }
+
+    // Marca un bloque como liberado; los punteros que lo apuntan quedan colgantes
+    #[wasm_bindgen]
+    pub fn free_block(&mut self, block_id: &str) -> Result<(), JsValue> {
+        let now = self.now();
+        let block = self
+            .memory_blocks
+            .get_mut(block_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))?;
+
+        if block.freed_at.is_some() {
+            return Err(JsValue::from_str(&format!("Double free of block: {}", block_id)));
+        }
+        block.freed_at = Some(now);
+        self.record_trace(block_id, None, "free".to_string(), false);
+        self.emit_event("block_freed", block_id);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Qué punteros quedarían colgantes y qué bloques se perderían al liberar, sin liberar nada
+    #[wasm_bindgen]
+    pub fn preview_free(&self, block_id: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.free_preview(block_id)?)
+    }
+
+    fn free_preview(&self, block_id: &str) -> Result<FreePreview, EngineError> {
+        if !self.memory_blocks.contains_key(block_id) {
+            return Err(EngineError(format!("Memory block not found: {}", block_id)));
+        }
+
+        let mut dangling: Vec<String> = self
+            .pointers
+            .values()
+            .filter(|pointer| self.target_of(pointer) == Some(block_id) && !self.is_pointer_dangling(pointer))
+            .map(|pointer| pointer.id.clone())
+            .collect();
+        dangling.sort();
+
+        // Bloques de heap cuyas únicas referencias están guardadas en bloques que desaparecen
+        let mut doomed: HashSet<String> = HashSet::from([block_id.to_string()]);
+        loop {
+            let newly_leaked: Vec<String> = self
+                .memory_blocks
+                .values()
+                .filter(|block| block.memory_type == "heap" && block.freed_at.is_none() && !doomed.contains(&block.id))
+                .filter(|block| {
+                    let incoming: Vec<_> = self.pointers_targeting(&block.id).collect();
+                    !incoming.is_empty()
+                        && incoming
+                            .iter()
+                            .all(|p| self.storage_block_of(p).is_some_and(|storage| doomed.contains(&storage.id)))
+                })
+                .map(|block| block.id.clone())
+                .collect();
+
+            if newly_leaked.is_empty() {
+                break;
+            }
+            doomed.extend(newly_leaked);
+        }
+        doomed.remove(block_id);
+        let mut leaked: Vec<String> = doomed.into_iter().collect();
+        leaked.sort();
+
+        Ok(FreePreview { dangling, leaked })
+    }
+
+    // Recuentos por tipo de memoria y de puntero, punteros colgantes/nulos, fugas, bytes reservados y profundidad de pila
+    #[wasm_bindgen]
+    pub fn scene_stats(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.stats())
+    }
+
+    fn stats(&self) -> SceneStats {
+        let mut blocks_by_type = BTreeMap::new();
+        for block in self.memory_blocks.values() {
+            *blocks_by_type.entry(block.memory_type.clone()).or_insert(0) += 1;
+        }
+        let mut pointers_by_kind = BTreeMap::new();
+        for pointer in self.pointers.values() {
+            *pointers_by_kind.entry(format!("{:?}", pointer.kind)).or_insert(0) += 1;
+        }
+
+        SceneStats {
+            blocks_by_type,
+            pointers_by_kind,
+            dangling_pointers: self.pointers.values().filter(|p| self.is_pointer_dangling(p)).count(),
+            null_pointers: self.pointers.values().filter(|p| self.is_pointer_null(p)).count(),
+            leaked_blocks: self.leaked_blocks().len(),
+            total_allocated_bytes: self
+                .memory_blocks
+                .values()
+                .filter(|block| block.freed_at.is_none())
+                .filter_map(|block| block.size_bytes)
+                .map(u64::from)
+                .sum(),
+            stack_depth: self.stack_depth(),
+        }
+    }
+
+    // Bloques de heap vivos que no se alcanzan desde ningún bloque de pila o global vivo
+    fn leaked_blocks(&self) -> Vec<&MemoryBlock3D> {
+        let mut reachable = HashSet::new();
+        for root in self.memory_blocks.values().filter(|b| b.memory_type != "heap" && b.freed_at.is_none()) {
+            reachable.extend(self.reachable_from(&root.id));
+        }
+        self.memory_blocks
+            .values()
+            .filter(|b| b.memory_type == "heap" && b.freed_at.is_none() && !reachable.contains(&b.id))
+            .collect()
+    }
+
+    // Distancia (en saltos de puntero) desde el bloque raíz a cada bloque alcanzable, en anchura
+    #[wasm_bindgen]
+    pub fn compute_node_depths(&mut self, root_block_id: &str) -> Result<JsValue, JsValue> {
+        self.update_node_depths(root_block_id)?;
+        to_js(&self.node_depths)
+    }
+
+    fn update_node_depths(&mut self, root_block_id: &str) -> Result<(), EngineError> {
+        if !self.memory_blocks.contains_key(root_block_id) {
+            return Err(EngineError(format!("Memory block not found: {}", root_block_id)));
+        }
+
+        let mut depths = BTreeMap::new();
+        let mut queue = VecDeque::new();
+        depths.insert(root_block_id.to_string(), 0);
+        queue.push_back((root_block_id.to_string(), 0));
+        while let Some((block_id, depth)) = queue.pop_front() {
+            let targets: Vec<&str> = self
+                .outgoing_pointers(&block_id)
+                .filter_map(|p| self.target_of(p))
+                .filter(|target| self.memory_blocks.contains_key(*target))
+                .collect();
+            for target in targets {
+                if !depths.contains_key(target) {
+                    depths.insert(target.to_string(), depth + 1);
+                    queue.push_back((target.to_string(), depth + 1));
+                }
+            }
+        }
+
+        self.node_depths = depths;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Comprueba que lo alcanzable desde la raíz sea un árbol: informa de los nodos con más de un
+    // camino de llegada ("shared") y de los punteros que vuelven a un ancestro ("cycle")
+    #[wasm_bindgen]
+    pub fn check_tree_structure(&self, root_block_id: &str) -> Result<JsValue, JsValue> {
+        to_js(&self.tree_violations(root_block_id)?)
+    }
+
+    fn tree_violations(&self, root_block_id: &str) -> Result<Vec<TreeViolation>, EngineError> {
+        if !self.memory_blocks.contains_key(root_block_id) {
+            return Err(EngineError(format!("Memory block not found: {}", root_block_id)));
+        }
+
+        let mut visited = HashSet::new();
+        let mut ancestors = Vec::new();
+        let mut back_edges = Vec::new();
+        self.walk_tree(root_block_id, &mut visited, &mut ancestors, &mut back_edges);
+
+        let mut violations: Vec<TreeViolation> = Vec::new();
+        for (pointer_id, block_id) in back_edges.iter() {
+            violations.push(TreeViolation { kind: "cycle", block_id: block_id.clone(), pointer_ids: vec![pointer_id.clone()] });
+        }
+
+        // Aristas de entrada a cada nodo alcanzado, sin contar las que cierran ciclos
+        let mut incoming: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for block_id in &visited {
+            for pointer in self.outgoing_pointers(block_id) {
+                let Some(target) = self.target_of(pointer).filter(|t| visited.contains(*t)) else {
+                    continue;
+                };
+                if !back_edges.iter().any(|(id, _)| *id == pointer.id) {
+                    incoming.entry(target).or_default().push(pointer.id.clone());
+                }
+            }
+        }
+        for (block_id, mut pointer_ids) in incoming {
+            if pointer_ids.len() > 1 {
+                pointer_ids.sort();
+                violations.push(TreeViolation { kind: "shared", block_id: block_id.to_string(), pointer_ids });
+            }
+        }
+
+        violations.sort_by(|a, b| a.kind.cmp(b.kind).then_with(|| a.block_id.cmp(&b.block_id)));
+        Ok(violations)
+    }
+
+    // Recorrido en profundidad que anota las aristas hacia un bloque aún en la pila de ancestros
+    fn walk_tree(
+        &self,
+        block_id: &str,
+        visited: &mut HashSet<String>,
+        ancestors: &mut Vec<String>,
+        back_edges: &mut Vec<(String, String)>,
+    ) {
+        visited.insert(block_id.to_string());
+        ancestors.push(block_id.to_string());
+
+        let mut edges: Vec<(&str, &str)> = self
+            .outgoing_pointers(block_id)
+            .filter_map(|p| self.target_of(p).map(|target| (p.id.as_str(), target)))
+            .filter(|(_, target)| self.memory_blocks.contains_key(*target))
+            .collect();
+        edges.sort();
+        for (pointer_id, target) in edges {
+            if ancestors.iter().any(|id| id == target) {
+                back_edges.push((pointer_id.to_string(), target.to_string()));
+            } else if !visited.contains(target) {
+                self.walk_tree(target, visited, ancestors, back_edges);
+            }
+        }
+
+        ancestors.pop();
+    }
+
+    // Marca la zona inmediatamente posterior a los valid_slots del array como fuera de límites
+    #[wasm_bindgen]
+    pub fn show_array_bounds(&mut self, block_id: &str, valid_slots: usize) -> Result<(), JsValue> {
+        if !self.memory_blocks.contains_key(block_id) {
+            return Err(JsValue::from_str(&format!("Memory block not found: {}", block_id)));
+        }
+        if valid_slots == 0 {
+            return Err(JsValue::from_str("Array must have at least one valid slot"));
+        }
+
+        self.array_bounds.insert(block_id.to_string(), valid_slots);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_array_bounds(&mut self, block_id: &str) {
+        self.array_bounds.remove(block_id);
+        self.mark_dirty();
+    }
+
+    // Insignia "dN" en cada bloque con la última profundidad calculada
+    #[wasm_bindgen]
+    pub fn show_node_depths(&mut self, enabled: bool) {
+        self.show_node_depths = enabled;
+        self.mark_dirty();
+    }
+
+    // Grado de entrada/salida por bloque, salida por puntero (2 si hay doble indirección) y raíces/hojas
+    #[wasm_bindgen]
+    pub fn graph_metrics(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.compute_graph_metrics())
+    }
+
+    fn compute_graph_metrics(&self) -> GraphMetrics {
+        let blocks: BTreeMap<String, BlockDegree> = self
+            .memory_blocks
+            .keys()
+            .map(|id| {
+                let degree = BlockDegree {
+                    in_degree: self.pointers_targeting(id).count(),
+                    out_degree: self.outgoing_pointers(id).count(),
+                };
+                (id.clone(), degree)
+            })
+            .collect();
+
+        let pointers = self
+            .pointers
+            .values()
+            .map(|pointer| {
+                let out_degree = match self.target_of(pointer) {
+                    Some(target) if self.outgoing_pointers(target).next().is_some() => 2,
+                    Some(_) => 1,
+                    None => 0,
+                };
+                (pointer.id.clone(), out_degree)
+            })
+            .collect();
+
+        let roots = blocks.values().filter(|d| d.in_degree == 0).count();
+        let leaves = blocks.values().filter(|d| d.out_degree == 0).count();
+        GraphMetrics { blocks, pointers, roots, leaves }
+    }
+
+    // Punteros con destino guardados dentro del bloque dado
+    fn outgoing_pointers<'a>(&'a self, block_id: &'a str) -> impl Iterator<Item = &'a Pointer3D> + 'a {
+        self.pointers.values().filter(move |p| {
+            self.target_of(p).is_some() && self.storage_block_of(p).is_some_and(|storage| storage.id == block_id)
+        })
+    }
+
+    // Ids de bloques ordenados por address, size_bytes, value o allocated_at; los que no tienen el campo van al final
+    #[wasm_bindgen]
+    pub fn blocks_sorted_by(&self, field: &str, ascending: bool) -> Result<JsValue, JsValue> {
+        to_js(&self.sorted_block_ids(field, ascending)?)
+    }
+
+    fn sorted_block_ids(&self, field: &str, ascending: bool) -> Result<Vec<&str>, EngineError> {
+        use std::cmp::Ordering;
+
+        let compare: fn(&MemoryBlock3D, &MemoryBlock3D) -> Option<Ordering> = match field {
+            "address" => |a, b| Some(a.address?.cmp(&b.address?)),
+            "size_bytes" => |a, b| Some(a.size_bytes?.cmp(&b.size_bytes?)),
+            "value" => |a, b| Some(compare_values(a.value.as_deref()?, b.value.as_deref()?)),
+            "allocated_at" | "allocation_time" => |a, b| Some(a.allocated_at?.total_cmp(&b.allocated_at?)),
+            other => return Err(EngineError(format!("Unknown sort field: {}", other))),
+        };
+        let has_field = |block: &MemoryBlock3D| compare(block, block).is_some();
+
+        let mut blocks: Vec<&MemoryBlock3D> = self.memory_blocks.values().collect();
+        blocks.sort_by(|a, b| {
+            let order = match (has_field(a), has_field(b)) {
+                (true, true) => {
+                    let order = compare(a, b).unwrap();
+                    if ascending { order } else { order.reverse() }
+                }
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => Ordering::Equal,
+            };
+            order.then_with(|| a.id.cmp(&b.id))
+        });
+
+        Ok(blocks.iter().map(|block| block.id.as_str()).collect())
+    }
+
+    // Un puntero cuelga si su destino ya no existe o fue liberado
+    #[wasm_bindgen]
+    pub fn is_dangling(&self, pointer_id: &str) -> Result<bool, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        Ok(self.is_pointer_dangling(pointer))
+    }
+
+    fn is_pointer_dangling(&self, pointer: &Pointer3D) -> bool {
+        match self.target_of(pointer) {
+            Some(target) => self.memory_blocks.get(target).is_none_or(|block| block.freed_at.is_some()),
+            None => false,
+        }
+    }
+
+    fn pointers_targeting<'a>(&'a self, block_id: &'a str) -> impl Iterator<Item = &'a Pointer3D> + 'a {
+        self.pointers.values().filter(move |p| self.target_of(p) == Some(block_id))
+    }
+
+    // Ancla el origen del puntero a la variable que lo guarda: el inicio sigue al bloque al moverse
+    #[wasm_bindgen]
+    pub fn set_source_block(&mut self, pointer_id: &str, block_id: Option<String>) -> Result<(), JsValue> {
+        self.ensure_unlocked(pointer_id)?;
+        if let Some(ref block_id) = block_id {
+            if !self.memory_blocks.contains_key(block_id) {
+                return Err(JsValue::from_str(&format!("Memory block not found: {}", block_id)));
+            }
+        }
+        let pointer = self
+            .pointers
+            .get_mut(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        pointer.source_block_id = block_id;
+        self.sync_pointer_sources(None);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Lleva el inicio de los punteros anclados (a `block_id`, o a cualquier bloque) al ancla de su bloque origen
+    fn sync_pointer_sources(&mut self, block_id: Option<&str>) {
+        for pointer in self.pointers.values_mut() {
+            let Some(source_id) = pointer.source_block_id.as_deref() else {
+                continue;
+            };
+            if block_id.is_some_and(|id| id != source_id) {
+                continue;
+            }
+            if let Some((x, y, z)) = self.memory_blocks.get(source_id).map(block_anchor) {
+                pointer.start_x = x;
+                pointer.start_y = y;
+                pointer.start_z = z;
+            }
+        }
+    }
+
+    // Variable que guarda el puntero: su source_block_id o, si no, el bloque que contiene su origen
+    fn storage_block_of(&self, pointer: &Pointer3D) -> Option<&MemoryBlock3D> {
+        let source = pointer.source_block_id.as_deref().and_then(|id| self.memory_blocks.get(id));
+        if let Some(block) = source.filter(|block| block.freed_at.is_none()) {
+            return Some(block);
+        }
+        let (x, y) = self.project(pointer.start_x, pointer.start_y, pointer.start_z);
+        self.memory_blocks
+            .values()
+            .filter(|block| block.freed_at.is_none() && distance_to_rect(x, y, self.block_rect(block)) == 0.0)
+            .min_by(|a, b| (a.width * a.height).total_cmp(&(b.width * b.height)))
+    }
+
+    // Profundidad de la pila: número de marcos distintos con bloques vivos
+    #[wasm_bindgen]
+    pub fn stack_depth(&self) -> usize {
+        self.memory_blocks
+            .values()
+            .filter(|block| block.freed_at.is_none())
+            .filter_map(|block| block.frame_id.as_deref())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    #[wasm_bindgen]
+    pub fn show_depth_meter(&mut self, enabled: bool) {
+        self.show_depth_meter = enabled;
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn set_max_stack_depth(&mut self, max: usize) {
+        self.max_stack_depth = max.max(1);
+        self.mark_dirty();
+    }
+
+    // Asigna un bloque a una región/arena (None lo saca de cualquiera)
+    #[wasm_bindgen]
+    pub fn set_region(&mut self, block_id: &str, region_id: Option<String>) -> Result<(), JsValue> {
+        let block = self
+            .memory_blocks
+            .get_mut(block_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))?;
+        block.region_id = region_id;
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Punteros guardados en una región cuyo destino está en otra distinta
+    #[wasm_bindgen]
+    pub fn find_cross_region_pointers(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.cross_region_pointers())
+    }
+
+    fn cross_region_pointers(&self) -> Vec<RegionCrossing> {
+        let mut crossings: Vec<RegionCrossing> = self.pointers.values().filter_map(|p| self.region_crossing(p)).collect();
+        crossings.sort_by(|a, b| a.pointer_id.cmp(&b.pointer_id));
+        crossings
+    }
+
+    // Cada puntero toma el color de su bloque destino (rojo si cuelga, gris si es nulo)
+    #[wasm_bindgen]
+    pub fn match_target_color(&mut self, enabled: bool) {
+        self.match_target_color = enabled;
+        self.mark_dirty();
+    }
+
+    // Color para dibujar los punteros que cruzan regiones (None usa el color propio de cada puntero)
+    #[wasm_bindgen]
+    pub fn set_cross_region_color(&mut self, color: Option<String>) {
+        self.cross_region_color = color;
+        self.mark_dirty();
+    }
+
+    fn region_crossing(&self, pointer: &Pointer3D) -> Option<RegionCrossing> {
+        let source = self.storage_block_of(pointer)?.region_id.as_deref()?;
+        let target = self.memory_blocks.get(self.target_of(pointer)?)?.region_id.as_deref()?;
+        (source != target).then(|| RegionCrossing {
+            pointer_id: pointer.id.clone(),
+            source_region: source.to_string(),
+            target_region: target.to_string(),
+        })
+    }
+
+    // Punteros que apuntan al bloque donde están guardados (`p = p;`) y referencias ligadas a sí mismas
+    #[wasm_bindgen]
+    pub fn check_self_references(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.self_references())
+    }
+
+    fn self_references(&self) -> Vec<SelfReference> {
+        let mut flagged: Vec<SelfReference> = self.pointers.values().filter_map(|p| self.self_reference_of(p)).collect();
+        flagged.sort_by(|a, b| a.pointer_id.cmp(&b.pointer_id));
+        flagged
+    }
+
+    #[wasm_bindgen]
+    pub fn show_self_references(&mut self, enabled: bool) {
+        self.show_self_references = enabled;
+        self.mark_dirty();
+    }
+
+    fn self_reference_of(&self, pointer: &Pointer3D) -> Option<SelfReference> {
+        let target = self.target_of(pointer)?;
+        let is_self = target == pointer.id || self.storage_block_of(pointer).is_some_and(|storage| storage.id == target);
+        is_self.then(|| SelfReference {
+            pointer_id: pointer.id.clone(),
+            block_id: target.to_string(),
+            kind: if pointer.kind == PointerKind::Reference { "reference" } else { "pointer" },
+        })
+    }
+
+    // Punteros guardados en una clase de memoria (stack/heap/global) que apuntan a otra distinta
+    #[wasm_bindgen]
+    pub fn check_pointer_storage_classes(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.storage_crossings())
+    }
+
+    fn storage_crossings(&self) -> Vec<StorageCrossing> {
+        let mut crossings: Vec<StorageCrossing> = self.pointers.values().filter_map(|p| self.storage_crossing(p)).collect();
+        crossings.sort_by(|a, b| a.pointer_id.cmp(&b.pointer_id));
+        crossings
+    }
+
+    // Insignias "stack→heap", "heap→stack", etc. en cada puntero que cruza clases de memoria
+    #[wasm_bindgen]
+    pub fn show_storage_classes(&mut self, enabled: bool) {
+        self.show_storage_classes = enabled;
+        self.mark_dirty();
+    }
+
+    fn storage_crossing(&self, pointer: &Pointer3D) -> Option<StorageCrossing> {
+        let storage = self.storage_block_of(pointer)?;
+        let target = self.memory_blocks.get(self.target_of(pointer)?)?;
+        (storage.memory_type != target.memory_type).then(|| StorageCrossing {
+            pointer_id: pointer.id.clone(),
+            storage_block_id: storage.id.clone(),
+            target_block_id: target.id.clone(),
+            crossing: format!("{}→{}", storage.memory_type, target.memory_type),
+        })
+    }
+
+    // Punteros con acceso de escritura (MutBorrow) sobre bloques de solo lectura
+    #[wasm_bindgen]
+    pub fn validate_const_writes(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.const_write_violations())
+    }
+
+    fn const_write_violations(&self) -> Vec<ConstWriteViolation> {
+        let mut violations: Vec<ConstWriteViolation> = self
+            .pointers
+            .values()
+            .filter(|pointer| pointer.kind.writes())
+            .filter_map(|pointer| {
+                let block = self.memory_blocks.get(self.target_of(pointer)?)?;
+                block.read_only.then(|| ConstWriteViolation {
+                    pointer_id: pointer.id.clone(),
+                    block_id: block.id.clone(),
+                })
+            })
+            .collect();
+        violations.sort_by(|a, b| a.pointer_id.cmp(&b.pointer_id));
+        violations
+    }
+
+    // Informe unificado de todos los validadores activos, de más a menos grave
+    #[wasm_bindgen]
+    pub fn diagnostics(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.collect_diagnostics())
+    }
+
+    fn collect_diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut report = |severity, code: &'static str, object_ids: Vec<String>, message: String| {
+            if !self.disabled_diagnostics.contains(code) {
+                diagnostics.push(Diagnostic { severity, code, object_ids, message });
+            }
+        };
+
+        for pointer in self.pointers.values() {
+            let id = pointer.id.clone();
+            if self.is_pointer_dangling(pointer) {
+                let target = self.target_of(pointer).unwrap_or_default().to_string();
+                let message = format!("{} points to freed or missing block {}", id, target);
+                report(Severity::Error, "dangling_pointer", vec![id.clone(), target], message);
+            }
+            if pointer.pending {
+                report(Severity::Warning, "uninitialized_pointer", vec![id.clone()], format!("{} has no target yet", id));
+            }
+            if let (Some(address), Some(stride)) = (pointer.target_address, pointer.stride_bytes) {
+                if stride > 1 && address % stride as u64 != 0 {
+                    let message = format!("{} address 0x{:x} is not aligned to {} bytes", id, address, stride);
+                    report(Severity::Warning, "misaligned_pointer", vec![id.clone()], message);
+                }
+            }
+            if let Some(block) = self.target_of(pointer).and_then(|t| self.memory_blocks.get(t)).filter(|b| b.moved_from) {
+                let message = format!("{} points to moved-from block {}", id, block.id);
+                report(Severity::Warning, "use_after_move", vec![id.clone(), block.id.clone()], message);
+            }
+            if let Some(found) = self.self_reference_of(pointer) {
+                let message = format!("{} refers to its own storage {}", id, found.block_id);
+                report(Severity::Warning, "self_reference", vec![id.clone(), found.block_id], message);
+            }
+            if let Some(crossing) = self.storage_crossing(pointer) {
+                let message = format!("{} crosses storage classes ({})", id, crossing.crossing);
+                report(Severity::Info, "storage_class_crossing", vec![id.clone()], message);
+            }
+            if let Some(crossing) = self.region_crossing(pointer) {
+                let message = format!("{} crosses from region {} to {}", id, crossing.source_region, crossing.target_region);
+                report(Severity::Info, "cross_region_pointer", vec![id.clone()], message);
+            }
+        }
+
+        for violation in self.const_write_violations() {
+            let message = format!("{} can write to read-only block {}", violation.pointer_id, violation.block_id);
+            report(Severity::Error, "const_write", vec![violation.pointer_id, violation.block_id], message);
+        }
+        for deref in &self.null_derefs {
+            let message = format!("{} was dereferenced while {}", deref.pointer_id, deref.reason);
+            report(Severity::Error, "invalid_dereference", vec![deref.pointer_id.clone()], message);
+        }
+        for block in self.leaked_blocks() {
+            let message = format!("Heap block {} is unreachable and was never freed", block.id);
+            report(Severity::Warning, "leaked_block", vec![block.id.clone()], message);
+        }
+
+        diagnostics.sort_by(|a, b| {
+            a.severity.cmp(&b.severity).then(a.code.cmp(b.code)).then_with(|| a.object_ids.cmp(&b.object_ids))
+        });
+        diagnostics
+    }
+
+    // Activa o desactiva un validador de diagnostics() por su código
+    #[wasm_bindgen]
+    pub fn set_diagnostic_enabled(&mut self, code: &str, enabled: bool) {
+        if enabled {
+            self.disabled_diagnostics.remove(code);
+        } else {
+            self.disabled_diagnostics.insert(code.to_string());
+        }
+    }
+
+    // Coloca los bloques a lo largo de un eje horizontal según su instante de reserva
+    #[wasm_bindgen]
+    pub fn layout_by_time(&mut self, axis_y: f64, pixels_per_ms: f64) {
+        let origin_ms = self
+            .memory_blocks
+            .values()
+            .filter_map(|block| block.allocated_at)
+            .fold(f64::INFINITY, f64::min);
+        if !origin_ms.is_finite() {
+            return;
+        }
+
+        for block in self.memory_blocks.values_mut().filter(|b| !b.locked) {
+            if let Some(allocated_at) = block.allocated_at {
+                block.x = TIME_AXIS_LEFT + (allocated_at - origin_ms) * pixels_per_ms;
+            }
+        }
+        self.sync_pointer_sources(None);
+        self.time_axis = Some(TimeAxis { axis_y, pixels_per_ms, origin_ms });
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_time_axis(&mut self) {
+        self.time_axis = None;
+        self.mark_dirty();
+    }
+
+    // Fracción de solapamiento entre los rangos de bytes apuntados (0 disjuntos, 1 idénticos)
+    #[wasm_bindgen]
+    pub fn target_overlap_ratio(&self, id_a: &str, id_b: &str) -> Result<f64, JsValue> {
+        let (Some((block_a, start_a, end_a)), Some((block_b, start_b, end_b))) =
+            (self.target_byte_range(id_a)?, self.target_byte_range(id_b)?)
+        else {
+            return Ok(0.0);
+        };
+
+        // Sin direcciones solo podemos comparar rangos dentro del mismo bloque
+        let has_addresses = [block_a, block_b]
+            .iter()
+            .all(|id| self.memory_blocks.get(*id).is_some_and(|b| b.address.is_some()));
+        if block_a != block_b && !has_addresses {
+            return Ok(0.0);
+        }
+
+        let intersection = end_a.min(end_b).saturating_sub(start_a.max(start_b));
+        let union = end_a.max(end_b).saturating_sub(start_a.min(start_b));
+        if union == 0 {
+            return Ok(0.0);
+        }
+        Ok(intersection as f64 / union as f64)
+    }
+
+    // Bloque destino y rango [inicio, fin) de bytes apuntado (base 0 si el bloque no tiene dirección)
+    fn target_byte_range(&self, pointer_id: &str) -> Result<Option<(&str, u64, u64)>, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        let Some(block) = self.target_of(pointer).and_then(|id| self.memory_blocks.get(id)) else {
+            return Ok(None);
+        };
+
+        let size = block.size_bytes.unwrap_or(0) as u64;
+        let (offset, length) = pointer.target_range.unwrap_or((0, size));
+        // Direcciones y rangos vienen del JSON del usuario: se recortan al final del espacio de direcciones
+        let start = block.address.unwrap_or(0).saturating_add(offset);
+        Ok(Some((block.id.as_str(), start, start.saturating_add(length))))
+    }
+
+    // Zoom centrado en un punto de pantalla, limitado por set_zoom_limits
+    #[wasm_bindgen]
+    pub fn zoom_at(&mut self, screen_x: f64, screen_y: f64, factor: f64) {
+        let zoom = self.clamp_zoom(self.camera.zoom * factor);
+
+        // Mantener fijo el punto del mundo bajo el cursor
+        let world_x = (screen_x - self.camera.pan_x) / self.camera.zoom;
+        let world_y = (screen_y - self.camera.pan_y) / self.camera.zoom;
+        self.camera.pan_x = screen_x - world_x * zoom;
+        self.camera.pan_y = screen_y - world_y * zoom;
+        self.camera.zoom = zoom;
+        self.record_gesture();
+        self.mark_dirty();
+    }
+
+    // Desplaza la vista en píxeles de pantalla
+    #[wasm_bindgen]
+    pub fn pan_by(&mut self, dx: f64, dy: f64) {
+        self.camera.pan_x += dx;
+        self.camera.pan_y += dy;
+        self.record_gesture();
+        self.mark_dirty();
+    }
+
+    // Ajusta zoom y desplazamiento para que toda la escena quepa en el canvas
+    #[wasm_bindgen]
+    pub fn fit_to_canvas(&mut self, width: f64, height: f64, padding: f64) {
+        let Some((min_x, min_y, max_x, max_y)) = self.scene_bounds() else {
+            self.camera = Camera::default();
+            return;
+        };
+
+        let scene_width = (max_x - min_x).max(1.0);
+        let scene_height = (max_y - min_y).max(1.0);
+        let available_width = (width - padding * 2.0).max(1.0);
+        let available_height = (height - padding * 2.0).max(1.0);
+        let zoom = self.clamp_zoom((available_width / scene_width).min(available_height / scene_height));
+
+        self.camera = Camera {
+            zoom,
+            pan_x: (width - scene_width * zoom) / 2.0 - min_x * zoom,
+            pan_y: (height - scene_height * zoom) / 2.0 - min_y * zoom,
+        };
+        self.record_gesture();
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn set_zoom_limits(&mut self, min: f64, max: f64) -> Result<(), JsValue> {
+        if !(min > 0.0 && min <= max && max.is_finite()) {
+            return Err(JsValue::from_str(&format!("Invalid zoom limits: {} - {}", min, max)));
+        }
+
+        self.min_zoom = min;
+        self.max_zoom = max;
+        self.camera.zoom = self.clamp_zoom(self.camera.zoom);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Convierte un punto de pantalla (píxeles CSS) a coordenadas proyectadas del mundo
+    #[wasm_bindgen]
+    pub fn screen_to_world(&self, screen_x: f64, screen_y: f64) -> Result<JsValue, JsValue> {
+        let (x, y) = self.unproject_screen(screen_x, screen_y);
+        to_js(&Point2D { x, y })
+    }
+
+    #[wasm_bindgen]
+    pub fn world_to_screen(&self, world_x: f64, world_y: f64) -> Result<JsValue, JsValue> {
+        let x = world_x * self.camera.zoom + self.camera.pan_x;
+        let y = world_y * self.camera.zoom + self.camera.pan_y;
+        to_js(&Point2D { x, y })
+    }
+
+    // Muestra en una esquina las coordenadas de pantalla y de mundo del cursor
+    #[wasm_bindgen]
+    pub fn show_cursor_readout(&mut self, screen_x: f64, screen_y: f64, enabled: bool) {
+        self.cursor_readout = enabled.then_some((screen_x, screen_y));
+        self.mark_dirty();
+    }
+
+    fn unproject_screen(&self, screen_x: f64, screen_y: f64) -> (f64, f64) {
+        (
+            (screen_x - self.camera.pan_x) / self.camera.zoom,
+            (screen_y - self.camera.pan_y) / self.camera.zoom,
+        )
+    }
+
+    // Regla superior que traduce la coordenada x del mundo a direcciones de memoria
+    #[wasm_bindgen]
+    pub fn show_address_ruler(&mut self, base_address: u64, bytes_per_pixel: f64) {
+        self.address_ruler = Some(AddressRuler { base_address, bytes_per_pixel });
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn hide_address_ruler(&mut self) {
+        self.address_ruler = None;
+        self.mark_dirty();
+    }
+
+    // Punto láser controlado por el presentador, en coordenadas de pantalla
+    #[wasm_bindgen]
+    pub fn set_presenter_pointer(&mut self, x: f64, y: f64, visible: bool) {
+        if !visible {
+            self.presenter_pointer = None;
+            self.presenter_trail.clear();
+            self.mark_dirty();
+            return;
+        }
+
+        if let Some(previous) = self.presenter_pointer {
+            self.presenter_trail.push_back(previous);
+            if self.presenter_trail.len() > PRESENTER_TRAIL_LENGTH {
+                self.presenter_trail.pop_front();
+            }
+        }
+        self.presenter_pointer = Some((x, y));
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_zoom(&self) -> f64 {
+        self.camera.zoom
+    }
+
+    fn clamp_zoom(&self, zoom: f64) -> f64 {
+        zoom.clamp(self.min_zoom, self.max_zoom)
+    }
+
+    // Límites proyectados de toda la escena: (min_x, min_y, max_x, max_y)
+    fn scene_bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut points = Vec::new();
+        for block in self.memory_blocks.values() {
+            let (x, y, w, h) = self.block_rect(block);
+            points.push((x, y));
+            points.push((x + w, y + h));
+        }
+        for pointer in self.pointers.values() {
+            points.push(self.project(pointer.start_x, pointer.start_y, pointer.start_z));
+            points.push(self.project(pointer.end_x, pointer.end_y, pointer.end_z));
+            // La curva cuadrática queda dentro del triángulo de sus puntos de control
+            if let Some((x, y, z)) = pointer.control_point {
+                points.push(self.project(x, y, z));
+            }
+        }
+
+        points.into_iter().fold(None, |bounds, (x, y)| match bounds {
+            None => Some((x, y, x, y)),
+            Some((min_x, min_y, max_x, max_y)) => Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))),
+        })
+    }
+
+    // Tamaño necesario para ver la escena completa con zoom 1.0, con el pan que la sitúa dentro del margen
+    #[wasm_bindgen]
+    pub fn required_canvas_size(&self, padding: f64) -> Result<JsValue, JsValue> {
+        to_js(&self.canvas_size(padding))
+    }
+
+    fn canvas_size(&self, padding: f64) -> CanvasSize {
+        let padding = padding.max(0.0);
+        let (min_x, min_y, max_x, max_y) = self.scene_bounds().unwrap_or((0.0, 0.0, 0.0, 0.0));
+
+        CanvasSize {
+            width: (max_x - min_x + padding * 2.0).ceil(),
+            height: (max_y - min_y + padding * 2.0).ceil(),
+            pan_x: padding - min_x,
+            pan_y: padding - min_y,
+        }
+    }
+
+    // Relación entre píxeles físicos del canvas y píxeles CSS
+    #[wasm_bindgen]
+    pub fn set_device_pixel_ratio(&mut self, ratio: f64) {
+        self.device_pixel_ratio = if ratio.is_finite() && ratio > 0.0 { ratio } else { 1.0 };
+        self.mark_dirty();
+    }
+
+    // En modo hairline los trazos mantienen su grosor en píxeles físicos sea cual sea el DPR
+    #[wasm_bindgen]
+    pub fn set_hairline(&mut self, enabled: bool) {
+        self.hairline = enabled;
+        self.mark_dirty();
+    }
+
+    fn set_stroke_width(&self, context: &CanvasRenderingContext2d, nominal: f64) {
+        context.set_line_width(self.effective_line_width(nominal));
+    }
+
+    fn effective_line_width(&self, nominal: f64) -> f64 {
+        if self.hairline {
+            nominal / self.device_pixel_ratio
+        } else {
+            nominal
+        }
+    }
+
+    // Dibuja los bloques como cajas extruidas usando su profundidad
+    #[wasm_bindgen]
+    pub fn render_3d_boxes(&mut self, enabled: bool) {
+        self.render_3d_boxes = enabled;
+        self.mark_dirty();
+    }
+
+    // Muestra el tipo del puntero en origen y destino, marcando las conversiones
+    #[wasm_bindgen]
+    pub fn set_type_flow(&mut self, enabled: bool) {
+        self.type_flow = enabled;
+        self.mark_dirty();
+    }
+
+    // Curvar los punteros más cuanto más niveles de indirección tengan (`**pp` frente a `*p`)
+    #[wasm_bindgen]
+    pub fn show_indirection_bend(&mut self, enabled: bool) {
+        self.indirection_bend = enabled;
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_indirection_depth(&self, pointer_id: &str) -> Result<usize, JsValue> {
+        let pointer = self
+            .pointers
+            .get(pointer_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Pointer not found: {}", pointer_id)))?;
+        Ok(self.indirection_depth(pointer))
+    }
+
+    // 1 para un puntero a un valor; 1 + la mayor profundidad de los punteros guardados en su destino
+    fn indirection_depth(&self, pointer: &Pointer3D) -> usize {
+        let mut visited = HashSet::new();
+        self.indirection_depth_from(pointer, &mut visited)
+    }
+
+    fn indirection_depth_from<'a>(&'a self, pointer: &'a Pointer3D, visited: &mut HashSet<&'a str>) -> usize {
+        let Some(target) = self.target_of(pointer) else {
+            return 1;
+        };
+        if !visited.insert(pointer.id.as_str()) {
+            return 1;
+        }
+
+        let deepest = self
+            .outgoing_pointers(target)
+            .map(|inner| self.indirection_depth_from(inner, visited))
+            .max()
+            .unwrap_or(0);
+        visited.remove(pointer.id.as_str());
+        1 + deepest
+    }
+
+    // Limpia el lienzo y aplica el suavizado de imágenes antes de dibujar un fotograma
+    fn prepare_surface(&self, surface: &impl RenderSurface, width: f64, height: f64) {
+        surface.clear_rect(0.0, 0.0, width, height);
+        surface.set_image_smoothing_enabled(self.image_smoothing);
+    }
+
+    // Suavizado de imágenes: desactivarlo da un renderizado nítido tipo pixel-art
+    #[wasm_bindgen]
+    pub fn set_image_smoothing(&mut self, enabled: bool) {
+        self.image_smoothing = enabled;
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        // Avisar de cada baja (y de la selección perdida) antes de vaciar la escena
+        let mut pointer_ids: Vec<String> = self.pointers.keys().cloned().collect();
+        pointer_ids.sort();
+        let mut block_ids: Vec<String> = self.memory_blocks.keys().cloned().collect();
+        block_ids.sort();
+        self.pointers.clear();
+        self.memory_blocks.clear();
+        for id in &pointer_ids {
+            self.emit_event("pointer_removed", id);
+        }
+        for id in &block_ids {
+            self.emit_event("block_removed", id);
+        }
+        if self.selected_id.take().is_some() {
+            self.emit_event("selection_changed", "");
+        }
+        self.value_tweens.clear();
+        self.color_tweens.clear();
+        self.pointer_tweens.clear();
+        self.block_slides.clear();
+        self.stride_indicators.clear();
+        self.block_flashes.clear();
+        self.crash_bursts.clear();
+        self.null_derefs.clear();
+        self.trace.clear();
+        self.hex_dumps.clear();
+        self.highlighted = None;
+        self.selected_id = None;
+        self.comparisons.clear();
+        self.pointer_groups.clear();
+        self.refcount_history.clear();
+        self.node_depths.clear();
+        self.array_bounds.clear();
+        self.gc_marked = None;
+        self.gc_mark_schedule.clear();
+        self.block_fades.clear();
+        self.pointer_fades.clear();
+        self.block_growths.clear();
+        self.time_axis = None;
+        self.mark_dirty();
+    }
+
+    #[wasm_bindgen]
+    pub fn export_scene(&self) -> String {
+        serde_json::to_string(&self.to_scene()).unwrap()
+    }
+
+    #[wasm_bindgen]
+    pub fn import_scene(&mut self, json: &str) -> Result<(), JsValue> {
+        Ok(self.load_scene(json)?)
+    }
+
+    fn load_scene(&mut self, json: &str) -> Result<(), EngineError> {
+        let scene: Scene =
+            serde_json::from_str(json).map_err(|e| EngineError(format!("Invalid scene JSON: {}", e)))?;
+        ensure_finite(scene.pointers.iter(), scene.memory_blocks.iter())?;
+
+        self.reset();
+        for ptr in scene.pointers {
+            self.insert_pointer(ptr);
+        }
+        for block in scene.memory_blocks {
+            self.insert_block(block);
+        }
+        self.sync_pointer_sources(None);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Importa bloques (id,type,x,y,value) y punteros (id,from,to) desde CSV
+    #[wasm_bindgen]
+    pub fn import_csv(&mut self, blocks_csv: &str, pointers_csv: &str) -> Result<(), JsValue> {
+        let mut blocks = Vec::new();
+        for (row, fields) in csv_rows(blocks_csv) {
+            let [id, memory_type, x, y, value @ ..] = fields.as_slice() else {
+                return Err(JsValue::from_str(&format!("Blocks CSV row {}: expected id,type,x,y,value", row)));
+            };
+            let (Ok(x), Ok(y)) = (x.parse::<f64>(), y.parse::<f64>()) else {
+                return Err(JsValue::from_str(&format!("Blocks CSV row {}: invalid coordinates", row)));
+            };
+            let value = value.first().filter(|v| !v.is_empty()).map(|v| v.to_string());
+
+            blocks.push(MemoryBlock3D {
+                id: id.to_string(),
+                x,
+                y,
+                width: CSV_BLOCK_WIDTH,
+                height: CSV_BLOCK_HEIGHT,
+                color: default_block_color(memory_type).to_string(),
+                value,
+                memory_type: memory_type.to_string(),
+                allocated_at: Some(self.now()),
+                ..Default::default()
+            });
+        }
+
+        let mut pointers = Vec::new();
+        for (row, fields) in csv_rows(pointers_csv) {
+            let [id, from, to] = fields.as_slice() else {
+                return Err(JsValue::from_str(&format!("Pointers CSV row {}: expected id,from,to", row)));
+            };
+            let find = |block_id: &str| {
+                blocks
+                    .iter()
+                    .find(|b| b.id == block_id)
+                    .or_else(|| self.memory_blocks.get(block_id))
+                    .map(block_anchor)
+                    .ok_or_else(|| JsValue::from_str(&format!("Pointers CSV row {}: unknown block {}", row, block_id)))
+            };
+            let (start, end) = (find(from)?, find(to)?);
+
+            pointers.push(Pointer3D {
+                id: id.to_string(),
+                start_x: start.0,
+                start_y: start.1,
+                start_z: start.2,
+                end_x: end.0,
+                end_y: end.1,
+                end_z: end.2,
+                color: CSV_POINTER_COLOR.to_string(),
+                target_block_id: Some(to.to_string()),
+                ..Default::default()
+            });
+        }
+
+        // Aplicar solo si todas las filas son válidas
+        ensure_finite(pointers.iter(), blocks.iter())?;
+        for block in blocks {
+            self.insert_block(block);
+        }
+        for pointer in pointers {
+            self.insert_pointer(pointer);
+        }
+        self.sync_pointer_sources(None);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    // Semántica de movimiento: el destino recibe el valor y el origen queda vacío (moved-from)
+    #[wasm_bindgen]
+    pub fn move_value(&mut self, src_id: &str, dst_id: &str) -> Result<(), JsValue> {
+        if !self.memory_blocks.contains_key(dst_id) {
+            return Err(JsValue::from_str(&format!("Memory block not found: {}", dst_id)));
+        }
+        let source = self
+            .memory_blocks
+            .get_mut(src_id)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", src_id)))?;
+        if source.moved_from {
+            return Err(JsValue::from_str(&format!("Use after move of block: {}", src_id)));
+        }
+
+        let value = source.value.take();
+        source.moved_from = src_id != dst_id;
+        self.value_tweens.remove(src_id);
+        if let Some(destination) = self.memory_blocks.get_mut(dst_id) {
+            destination.value = value;
+            destination.moved_from = false;
+        }
+        self.record_trace(src_id, Some(dst_id), "std::move".to_string(), false);
+        self.mark_dirty();
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn is_moved_from(&self, block_id: &str) -> Result<bool, JsValue> {
+        self.memory_blocks
+            .get(block_id)
+            .map(|block| block.moved_from)
+            .ok_or_else(|| JsValue::from_str(&format!("Memory block not found: {}", block_id)))
+    }
+
+    // Asigna tonos equiespaciados a los objetos sin color, sin tocar los que ya lo tienen
+    #[wasm_bindgen]
+    pub fn auto_color_unset(&mut self) -> usize {
+        let mut ids: Vec<String> = self
+            .memory_blocks
+            .values()
+            .filter(|block| block.color.trim().is_empty())
+            .map(|block| block.id.clone())
+            .chain(self.pointers.values().filter(|p| p.color.trim().is_empty()).map(|p| p.id.clone()))
+            .collect();
+        ids.sort();
+
+        let count = ids.len();
+        for (i, id) in ids.iter().enumerate() {
+            let color = hsl_to_hex(i as f64 * 360.0 / count as f64, 0.65, 0.55);
+            if let Some(block) = self.memory_blocks.get_mut(id) {
+                block.color = color;
+            } else if let Some(pointer) = self.pointers.get_mut(id) {
+                pointer.color = color;
+            }
+        }
+        self.mark_dirty();
+        count
+    }
+
+    // Tamaño en bytes del JSON exportado, para avisar de escenas grandes
+    #[wasm_bindgen]
+    pub fn scene_size_bytes(&self) -> usize {
+        self.export_scene().len()
+    }
+
+    // Hash estable de posiciones y tamaños (sin colores ni valores) para saber si hace falta re-layout
+    #[wasm_bindgen]
+    pub fn layout_hash(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let mut blocks: Vec<&MemoryBlock3D> = self.memory_blocks.values().collect();
+        blocks.sort_by(|a, b| a.id.cmp(&b.id));
+        for block in blocks {
+            block.id.hash(&mut hasher);
+            for v in [block.x, block.y, block.z, block.width, block.height, block.depth] {
+                v.to_bits().hash(&mut hasher);
+            }
+        }
+
+        let mut pointers: Vec<&Pointer3D> = self.pointers.values().collect();
+        pointers.sort_by(|a, b| a.id.cmp(&b.id));
+        for pointer in pointers {
+            pointer.id.hash(&mut hasher);
+            for v in [pointer.start_x, pointer.start_y, pointer.start_z, pointer.end_x, pointer.end_y, pointer.end_z] {
+                v.to_bits().hash(&mut hasher);
+            }
+            pointer.control_point.map(|(x, y, z)| (x.to_bits(), y.to_bits(), z.to_bits())).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // Subconjunto de la escena necesario para generar miniaturas
+    #[wasm_bindgen]
+    pub fn export_render_model(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.render_model())
+    }
+
+    fn render_model(&self) -> RenderModel {
+        let scene = self.to_scene();
+
+        let blocks = scene
+            .memory_blocks
+            .iter()
+            .map(|block| {
+                let (x, y, width, height) = self.block_rect(block);
+                RenderBlock { x, y, width, height, color: block.color.clone(), text: block.value.clone() }
+            })
+            .collect();
+        let pointers = scene
+            .pointers
+            .iter()
+            .map(|pointer| {
+                let (x1, y1) = self.project(pointer.start_x, pointer.start_y, pointer.start_z);
+                let (x2, y2) = self.project(pointer.end_x, pointer.end_y, pointer.end_z);
+                RenderPointer { x1, y1, x2, y2, color: pointer.color.clone(), thickness: pointer.thickness }
+            })
+            .collect();
+
+        RenderModel { blocks, pointers }
+    }
+
+    // Fragmento HTML autocontenido que recrea y dibuja la escena (solo lectura)
+    #[wasm_bindgen]
+    pub fn export_embed_html(&self, width: u32, height: u32) -> String {
+        // Evitar que un "</script>" dentro de un valor cierre el bloque
+        let scene_json = self.export_scene().replace("</", "<\\/");
+
+        format!(
+            r#"<canvas id="pointer-quest-embed" width="{width}" height="{height}"></canvas>
+<script type="module">
+  import init, {{ AnimationEngine }} from './pointer_quest_wasm.js';
+  const scene = {scene_json};
+  await init();
+  const engine = new AnimationEngine();
+  engine.import_scene(JSON.stringify(scene));
+  engine.render('pointer-quest-embed');
+</script>
+"#
+        )
+    }
+
+    fn to_scene(&self) -> Scene {
+        // Ordenar por id para que la exportación sea determinista
+        let mut pointers: Vec<Pointer3D> = self.pointers.values().cloned().collect();
+        pointers.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut memory_blocks: Vec<MemoryBlock3D> = self.memory_blocks.values().cloned().collect();
+        memory_blocks.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Scene { pointers, memory_blocks }
+    }
+}
+
+impl Default for AnimationEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Lo que la preparación de un fotograma necesita del contexto 2D
+trait RenderSurface {
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64);
+    fn set_image_smoothing_enabled(&self, enabled: bool);
+}
+
+impl RenderSurface for CanvasRenderingContext2d {
+    fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        CanvasRenderingContext2d::clear_rect(self, x, y, width, height);
+    }
+
+    fn set_image_smoothing_enabled(&self, enabled: bool) {
+        CanvasRenderingContext2d::set_image_smoothing_enabled(self, enabled);
+    }
+}
+
+// Canvas del documento y su contexto 2D
+fn canvas_by_id(canvas_id: &str) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = window().unwrap().document().unwrap();
+    let canvas = document
+        .get_element_by_id(canvas_id)
+        .ok_or_else(|| JsValue::from_str(&format!("Canvas not found: {}", canvas_id)))?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    let context = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    Ok((canvas, context))
+}
+
+// Canvas fuera del documento para exportaciones y mediciones
+fn create_offscreen_canvas(width: u32, height: u32) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = window().unwrap().document().unwrap();
+    let canvas = document.create_element("canvas")?.dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width.max(1));
+    canvas.set_height(height.max(1));
+
+    let context = canvas
+        .get_context("2d")?
+        .unwrap()
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    Ok((canvas, context))
+}
+
+// Funciones de utilidad para debugging
+#[wasm_bindgen]
+pub fn log_memory_info() {
+    console::log_1(&"Pointer Quest WASM Engine Active".into());
+    console::log_1(&"WebAssembly memory initialized successfully".into());
+}
+
+#[wasm_bindgen]
+pub fn benchmark_performance(iterations: usize) -> f64 {
+    let start_time = window().unwrap().performance().unwrap().now();
+
+    let mut sum = 0.0;
+    for i in 0..iterations {
+        sum += (i as f64).sin();
+    }
+
+    std::hint::black_box(sum);
+
+    let end_time = window().unwrap().performance().unwrap().now();
+    let elapsed = end_time - start_time;
+
+    console::log_1(&format!("Benchmark: {} iterations in {}ms", iterations, elapsed).into());
+
+    elapsed
+}
+
+// Inicialización de WebAssembly
+#[wasm_bindgen(start)]
+pub fn main() {
+    console::log_1(&"Pointer Quest WebAssembly module loaded successfully! 🚀".into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Motor con reloj manual en t=0: no necesita `window` y avanza solo con set_manual_time
+    fn engine() -> AnimationEngine {
+        AnimationEngine::with_clock(Some(0.0))
+    }
+
+    fn block(id: &str, x: f64, y: f64) -> MemoryBlock3D {
+        MemoryBlock3D {
+            id: id.to_string(),
+            x,
+            y,
+            width: 80.0,
+            height: 40.0,
+            depth: 10.0,
+            color: "#4488ff".to_string(),
+            memory_type: "heap".to_string(),
+            ..MemoryBlock3D::default()
+        }
+    }
+
+    // Puntero guardado en `from` que apunta al ancla de `to`
+    fn link(engine: &mut AnimationEngine, id: &str, from: &str, to: &str) {
+        let (start_x, start_y, start_z) = block_anchor(&engine.memory_blocks[from]);
+        let (end_x, end_y, end_z) = block_anchor(&engine.memory_blocks[to]);
+        engine.insert_pointer(Pointer3D {
+            id: id.to_string(),
+            start_x,
+            start_y,
+            start_z,
+            end_x,
+            end_y,
+            end_z,
+            color: "#ff0000".to_string(),
+            target_block_id: Some(to.to_string()),
+            source_block_id: Some(from.to_string()),
+            ..Pointer3D::default()
+        });
+    }
+
+    // Avanza el reloj manual y ejecuta un fotograma
+    fn advance(engine: &mut AnimationEngine, time_ms: f64) {
+        engine.set_manual_time(time_ms);
+        engine.animate(0.0);
+    }
+
+    // Sustituye el callback de JS por una lista con el JSON de cada entrega
+    fn record_events(engine: &mut AnimationEngine) -> std::rc::Rc<std::cell::RefCell<Vec<String>>> {
+        let deliveries = std::rc::Rc::default();
+        engine.event_callback = Some(EventSink::Recorder(std::rc::Rc::clone(&deliveries)));
+        deliveries
+    }
+
+    #[test]
+    fn scene_size_matches_exported_json() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        assert_eq!(engine.scene_size_bytes(), engine.export_scene().len());
+        assert!(engine.scene_size_bytes() > 0);
+    }
+
+    #[test]
+    fn value_tween_passes_through_the_midpoint() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { value: Some("0".to_string()), ..block("counter", 0.0, 0.0) });
+        engine.animate_value_to("counter", 10.0, 1000.0).unwrap();
+
+        advance(&mut engine, 500.0);
+        let midpoint: f64 = engine.memory_blocks["counter"].value.as_deref().unwrap().parse().unwrap();
+        assert!((midpoint - 5.0).abs() < 0.5, "midpoint value was {}", midpoint);
+
+        advance(&mut engine, 1000.0);
+        assert_eq!(engine.memory_blocks["counter"].value.as_deref(), Some("10"));
+    }
+
+    #[test]
+    fn hex_dump_lays_out_rows_with_an_address_gutter() {
+        let dump = HexDump { bytes: (0..16).collect(), columns: 8 };
+
+        let rows = dump.rows(None);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|(_, cells)| cells.len() == 8));
+        assert_eq!(rows[0].0, "0000");
+        assert_eq!(rows[1].0, "0008");
+
+        // Con dirección, el canal muestra la dirección real de cada fila
+        let labels: Vec<String> = dump.rows(Some(0x1000)).into_iter().map(|(label, _)| label).collect();
+        assert_eq!(labels, ["00001000", "00001008"]);
+    }
+
+    #[test]
+    fn highlight_marks_only_matching_values() {
+        let mut engine = engine();
+        for (id, value) in [("a", "42"), ("b", "7"), ("c", "42")] {
+            engine.insert_block(MemoryBlock3D { value: Some(value.to_string()), ..block(id, 0.0, 0.0) });
+        }
+        engine.insert_block(block("empty", 0.0, 0.0));
+
+        let count = engine.highlight_matching(|value| Ok(value == Some("42"))).unwrap();
+        assert_eq!(count, 2);
+        assert!(engine.is_highlighted("a") && engine.is_highlighted("c"));
+        assert!(!engine.is_highlighted("b") && !engine.is_highlighted("empty"));
+    }
+
+    #[test]
+    fn image_smoothing_setting_is_applied_when_the_next_frame_is_prepared() {
+        #[derive(Default)]
+        struct RecordingSurface(std::cell::RefCell<Vec<String>>);
+
+        impl RenderSurface for RecordingSurface {
+            fn clear_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+                self.0.borrow_mut().push(format!("clear {} {} {} {}", x, y, width, height));
+            }
+
+            fn set_image_smoothing_enabled(&self, enabled: bool) {
+                self.0.borrow_mut().push(format!("smoothing {}", enabled));
+            }
+        }
+
+        let mut engine = engine();
+        let surface = RecordingSurface::default();
+        engine.prepare_surface(&surface, 800.0, 600.0);
+        assert_eq!(*surface.0.borrow(), ["clear 0 0 800 600", "smoothing true"]);
+
+        engine.set_image_smoothing(false);
+        assert!(engine.dirty);
+        let surface = RecordingSurface::default();
+        engine.prepare_surface(&surface, 800.0, 600.0);
+        assert_eq!(*surface.0.borrow(), ["clear 0 0 800 600", "smoothing false"]);
+    }
+
+    #[test]
+    fn snap_attaches_only_nearby_endpoints() {
+        let mut engine = engine();
+        engine.insert_block(block("target", 0.0, 0.0));
+        for (id, end_x) in [("near", 82.0), ("far", 2000.0)] {
+            engine.insert_pointer(Pointer3D { id: id.to_string(), start_x: -300.0, end_x, end_y: 20.0, ..Pointer3D::default() });
+        }
+
+        assert_eq!(engine.snap_to_nearest("near", 10.0).unwrap().as_deref(), Some("target"));
+        let near = &engine.pointers["near"];
+        assert_eq!(near.target_block_id.as_deref(), Some("target"));
+        assert_eq!((near.end_x, near.end_y, near.end_z), block_anchor(&engine.memory_blocks["target"]));
+
+        assert_eq!(engine.snap_to_nearest("far", 10.0).unwrap(), None);
+        assert_eq!(engine.pointers["far"].target_block_id, None);
+        assert_eq!(engine.pointers["far"].end_x, 2000.0);
+    }
+
+    #[test]
+    fn comparison_is_green_when_it_holds_and_red_otherwise() {
+        let mut engine = engine();
+        for (id, x) in [("x", 0.0), ("y", 200.0), ("z", 400.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "p", "x", "z");
+        link(&mut engine, "q", "y", "z");
+        link(&mut engine, "r", "y", "x");
+
+        engine.show_comparison("p", "q", "==").unwrap();
+        engine.show_comparison("p", "r", "==").unwrap();
+        let [same, different] = [&engine.comparisons[0], &engine.comparisons[1]];
+        assert_eq!(same.symbol(), "==");
+        assert_eq!(engine.comparison_color(same), "#2ECC71");
+        assert_eq!(engine.comparison_color(different), "#E74C3C");
+    }
+
+    #[test]
+    fn render_model_drops_metadata_but_keeps_geometry() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { ref_count: Some(2), address: Some(0x1000), ..block("a", 0.0, 0.0) });
+        engine.insert_block(block("b", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+
+        let full = engine.export_scene();
+        let model = serde_json::to_string(&engine.render_model()).unwrap();
+        for field in ["memory_type", "ref_count", "address", "target_block_id"] {
+            assert!(full.contains(field), "full export should contain {}", field);
+            assert!(!model.contains(field), "render model should omit {}", field);
+        }
+
+        let model = engine.render_model();
+        let first = &model.blocks[0];
+        assert_eq!((first.x, first.y, first.width, first.height), engine.block_rect(&engine.memory_blocks["a"]));
+        assert_eq!(first.color, "#4488ff");
+        assert_eq!(model.pointers[0].color, "#ff0000");
+    }
+
+    #[test]
+    fn text_color_contrasts_with_the_fill() {
+        assert_eq!(contrasting_text_color("#fafafa"), "#111111");
+        assert_eq!(contrasting_text_color("rgb(10, 10, 10)"), "white");
+    }
+
+    #[test]
+    fn hiding_a_group_hides_every_member() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        link(&mut engine, "q", "b", "a");
+        link(&mut engine, "outside", "a", "a");
+
+        engine.group_pointers("pair", vec!["p".to_string(), "q".to_string()]).unwrap();
+        engine.set_group_visible("pair", false).unwrap();
+        assert!(!engine.pointers["p"].visible);
+        assert!(!engine.pointers["q"].visible);
+        assert!(engine.pointers["outside"].visible);
+    }
+
+    #[test]
+    fn embed_html_contains_the_scene_and_a_sized_canvas() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+
+        let html = engine.export_embed_html(640, 480);
+        assert!(html.contains(&engine.export_scene()));
+        assert!(html.contains(r#"<canvas id="pointer-quest-embed" width="640" height="480">"#));
+    }
+
+    #[test]
+    fn zoom_is_clamped_to_the_configured_maximum() {
+        let mut engine = engine();
+        engine.set_zoom_limits(0.5, 3.0).unwrap();
+
+        engine.zoom_at(100.0, 100.0, 10.0);
+        assert_eq!(engine.camera.zoom, 3.0);
+        engine.zoom_at(100.0, 100.0, 0.01);
+        assert_eq!(engine.camera.zoom, 0.5);
+    }
+
+    #[test]
+    fn refcount_history_records_every_change() {
+        let mut engine = engine();
+        engine.insert_block(block("shared", 0.0, 0.0));
+
+        engine.increment_ref_count("shared").unwrap();
+        engine.increment_ref_count("shared").unwrap();
+        engine.increment_ref_count("shared").unwrap();
+        engine.decrement_ref_count("shared").unwrap();
+        engine.decrement_ref_count("shared").unwrap();
+        assert_eq!(engine.refcount_values("shared"), [1, 2, 3, 2, 1]);
+    }
+
+    #[test]
+    fn tag_bits_are_masked_out_of_the_address() {
+        let mut engine = engine();
+        engine.insert_pointer(Pointer3D { id: "p".to_string(), target_address: Some(0x1003), ..Pointer3D::default() });
+        engine.insert_pointer(Pointer3D {
+            id: "aligned".to_string(),
+            target_address: Some(0x1001),
+            stride_bytes: Some(4),
+            ..Pointer3D::default()
+        });
+
+        engine.set_tag_bits("p", Some(0b11), Some(2)).unwrap();
+        assert_eq!(engine.masked_target_address("p").unwrap(), Some(0x1000));
+
+        // El ancho sale de la alineación, no del valor de la etiqueta
+        engine.set_tag_bits("aligned", Some(0b01), None).unwrap();
+        assert_eq!(engine.masked_target_address("aligned").unwrap(), Some(0x1000));
+    }
+
+    #[test]
+    fn free_slot_avoids_overlap_and_stays_close() {
+        let mut engine = engine();
+        engine.insert_block(block("occupied", 0.0, 0.0));
+        let (bx, by, bw, bh) = engine.block_rect(&engine.memory_blocks["occupied"]);
+
+        let (x, y) = engine.free_slot_near(50.0, 30.0, bx + 5.0, by + 5.0);
+        assert!(!rects_overlap((x, y, 50.0, 30.0), (bx, by, bw, bh)));
+        let distance = ((x - bx - 5.0).powi(2) + (y - by - 5.0).powi(2)).sqrt();
+        assert!(distance <= bw.max(bh) + 50.0 + FREE_SLOT_GAP, "slot is {} px away", distance);
+    }
+
+    #[test]
+    fn swap_exchanges_targets_after_the_duration() {
+        let mut engine = engine();
+        for (id, x, y) in [("x", 0.0, 0.0), ("y", 200.0, 0.0), ("a", 0.0, 300.0), ("b", 200.0, 300.0)] {
+            engine.insert_block(block(id, x, y));
+        }
+        link(&mut engine, "p", "x", "a");
+        link(&mut engine, "q", "y", "b");
+        let (end_a, end_b) = (block_anchor(&engine.memory_blocks["a"]), block_anchor(&engine.memory_blocks["b"]));
+
+        engine.animate_swap("p", "q", 400.0).unwrap();
+        advance(&mut engine, 200.0);
+        assert_eq!(engine.pointers["p"].target_block_id.as_deref(), Some("a"));
+        advance(&mut engine, 400.0);
+
+        let (p, q) = (&engine.pointers["p"], &engine.pointers["q"]);
+        assert_eq!(p.target_block_id.as_deref(), Some("b"));
+        assert_eq!(q.target_block_id.as_deref(), Some("a"));
+        assert_eq!((p.end_x, p.end_y, p.end_z), end_b);
+        assert_eq!((q.end_x, q.end_y, q.end_z), end_a);
+    }
+
+    #[test]
+    fn hairline_divides_strokes_by_the_pixel_ratio() {
+        let mut engine = engine();
+        engine.set_device_pixel_ratio(2.0);
+        assert_eq!(engine.effective_line_width(3.0), 3.0);
+
+        engine.set_hairline(true);
+        assert_eq!(engine.effective_line_width(3.0), 1.5);
+    }
+
+    #[test]
+    fn timeline_spaces_blocks_by_allocation_time() {
+        let mut engine = engine();
+        for (id, time_ms) in [("first", 0.0), ("second", 100.0), ("third", 300.0)] {
+            engine.set_manual_time(time_ms);
+            engine.add_block(block(id, 500.0, 0.0));
+        }
+
+        engine.layout_by_time(200.0, 2.0);
+        let x = |id: &str| engine.memory_blocks[id].x;
+        assert!(x("first") < x("second") && x("second") < x("third"));
+        assert_eq!(x("second") - x("first"), 200.0);
+        assert_eq!(x("third") - x("second"), 400.0);
+    }
+
+    #[test]
+    fn lifetime_bar_spans_allocation_to_free() {
+        let mut engine = engine();
+        engine.add_block(block("temp", 0.0, 0.0));
+        engine.add_block(block("live", 0.0, 100.0));
+        engine.set_manual_time(100.0);
+        engine.free_block("temp").unwrap();
+        engine.layout_by_time(200.0, 1.5);
+
+        let axis = engine.time_axis.unwrap();
+        let temp = &engine.memory_blocks["temp"];
+        assert_eq!(engine.lifetime_span(temp, &axis), Some((TIME_AXIS_LEFT, TIME_AXIS_LEFT + 150.0)));
+        // El marcador de fin solo se dibuja para bloques liberados
+        assert_eq!(engine.lifetime_end_marker(temp, &axis), Some(TIME_AXIS_LEFT + 150.0));
+        let live = &engine.memory_blocks["live"];
+        assert_eq!(engine.lifetime_span(live, &axis), Some((TIME_AXIS_LEFT, TIME_AXIS_LEFT + 150.0)));
+        assert_eq!(engine.lifetime_end_marker(live, &axis), None);
+    }
+
+    #[test]
+    fn overlap_ratio_of_overlapping_ranges() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", 0.0, 0.0));
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(16), ..block("array", 200.0, 0.0) });
+        link(&mut engine, "p", "owner", "array");
+        link(&mut engine, "q", "owner", "array");
+        engine.pointers.get_mut("p").unwrap().target_range = Some((0, 12));
+        engine.pointers.get_mut("q").unwrap().target_range = Some((4, 12));
+
+        let ratio = engine.target_overlap_ratio("p", "q").unwrap();
+        assert!((ratio - 0.5).abs() < 1e-9, "ratio was {}", ratio);
+    }
+
+    #[test]
+    fn cursor_readout_shows_the_world_point_under_the_cursor() {
+        let mut engine = engine();
+        engine.zoom_at(0.0, 0.0, 2.0);
+        engine.show_cursor_readout(150.0, 90.0, true);
+
+        let (world_x, world_y) = engine.unproject_screen(150.0, 90.0);
+        let text = engine.cursor_readout_text().unwrap();
+        assert!(text.ends_with(&format!("world ({:.1}, {:.1})", world_x, world_y)), "readout was {}", text);
+        assert!(text.contains("world (75.0, 45.0)"));
+
+        engine.show_cursor_readout(150.0, 90.0, false);
+        assert_eq!(engine.cursor_readout_text(), None);
+    }
+
+    #[test]
+    fn locked_block_cannot_be_removed() {
+        let mut engine = engine();
+        engine.insert_block(block("fixed", 0.0, 0.0));
+        engine.set_locked("fixed", true).unwrap();
+
+        assert_eq!(engine.remove_block("fixed"), Err(EngineError("Object is locked: fixed".to_string())));
+        assert!(engine.memory_blocks.contains_key("fixed"));
+
+        engine.set_locked("fixed", false).unwrap();
+        engine.remove_memory_block("fixed").unwrap();
+        assert!(!engine.memory_blocks.contains_key("fixed"));
+    }
+
+    #[test]
+    fn stack_depth_counts_distinct_live_frames() {
+        let mut engine = engine();
+        for (id, frame) in [("main.argc", "main"), ("main.argv", "main"), ("parse.buf", "parse"), ("lex.tok", "lex")] {
+            engine.insert_block(MemoryBlock3D { frame_id: Some(frame.to_string()), ..block(id, 0.0, 0.0) });
+        }
+        engine.insert_block(block("heap", 0.0, 0.0));
+
+        assert_eq!(engine.stack_depth(), 3);
+    }
+
+    #[test]
+    fn speed_ramp_eases_between_the_endpoints() {
+        let mut engine = engine();
+        engine.ramp_animation_speed(3.0, 1000.0);
+
+        advance(&mut engine, 500.0);
+        let midpoint = engine.get_animation_speed();
+        assert!(midpoint > 1.0 && midpoint < 3.0, "midpoint speed was {}", midpoint);
+
+        advance(&mut engine, 1000.0);
+        assert_eq!(engine.get_animation_speed(), 3.0);
+        assert!(engine.speed_ramp.is_none());
+
+        // Un destino no finito no deja la velocidad en NaN
+        engine.ramp_animation_speed(f64::NAN, 1000.0);
+        advance(&mut engine, 2000.0);
+        assert!(engine.get_animation_speed().is_finite());
+    }
+
+    #[test]
+    fn animation_speed_stays_finite_and_in_range() {
+        let mut engine = engine();
+        engine.set_animation_speed(f64::NAN);
+        assert_eq!(engine.get_animation_speed(), 0.1);
+        engine.set_animation_speed(f64::INFINITY);
+        assert_eq!(engine.get_animation_speed(), 5.0);
+        engine.set_animation_speed(2.0);
+        assert_eq!(engine.get_animation_speed(), 2.0);
+    }
+
+    #[test]
+    fn mutable_borrow_of_read_only_memory_is_reported() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", 0.0, 0.0));
+        engine.insert_block(MemoryBlock3D { read_only: true, ..block("constant", 200.0, 0.0) });
+        link(&mut engine, "shared", "owner", "constant");
+        link(&mut engine, "writer", "owner", "constant");
+        engine.pointers.get_mut("writer").unwrap().kind = PointerKind::MutBorrow;
+
+        let violations = engine.const_write_violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].pointer_id.as_str(), violations[0].block_id.as_str()), ("writer", "constant"));
+    }
+
+    #[test]
+    fn csv_import_creates_blocks_and_linked_pointer() {
+        let mut engine = engine();
+        let blocks = "id,type,x,y,value\nptr,stack,0,0,\nnode,heap,200,0,42\n";
+        let pointers = "id,from,to\np,ptr,node\n";
+        engine.import_csv(blocks, pointers).unwrap();
+
+        assert_eq!(engine.memory_blocks.len(), 2);
+        assert_eq!(engine.memory_blocks["node"].value.as_deref(), Some("42"));
+        assert_eq!(engine.memory_blocks["ptr"].memory_type, "stack");
+        let pointer = &engine.pointers["p"];
+        assert_eq!(pointer.target_block_id.as_deref(), Some("node"));
+        assert_eq!((pointer.end_x, pointer.end_y, pointer.end_z), block_anchor(&engine.memory_blocks["node"]));
+    }
+
+    #[test]
+    fn extruded_box_draws_three_differently_shaded_faces() {
+        let box_block = MemoryBlock3D { color: "#4080c0".to_string(), ..block("box", 100.0, 100.0) };
+        let faces = box_faces(&box_block, &ISOMETRIC_PROJECTION);
+
+        // Junto con la cara frontal (color del bloque) se ven tres caras
+        assert_eq!(faces.len() + 1, 3);
+        let mut colors: Vec<&str> = faces.iter().map(|(_, color)| color.as_str()).collect();
+        colors.push(&box_block.color);
+        colors.sort();
+        colors.dedup();
+        assert_eq!(colors.len(), 3);
+    }
+
+    #[test]
+    fn presenter_dot_follows_the_pointer_and_hides() {
+        let mut engine = engine();
+        engine.set_presenter_pointer(40.0, 60.0, true);
+        engine.set_presenter_pointer(120.0, 80.0, true);
+        assert_eq!(engine.presenter_pointer, Some((120.0, 80.0)));
+        assert_eq!(engine.presenter_trail.back(), Some(&(40.0, 60.0)));
+
+        engine.set_presenter_pointer(120.0, 80.0, false);
+        assert_eq!(engine.presenter_pointer, None);
+        assert!(engine.presenter_trail.is_empty());
+    }
+
+    #[test]
+    fn free_preview_lists_pointers_without_freeing() {
+        let mut engine = engine();
+        for (id, x) in [("p_home", 0.0), ("q_home", 100.0), ("heap", 300.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "p", "p_home", "heap");
+        link(&mut engine, "q", "q_home", "heap");
+        let before = engine.export_scene();
+
+        let preview = engine.free_preview("heap").unwrap();
+        assert_eq!(preview.dangling, ["p", "q"]);
+        assert_eq!(engine.export_scene(), before);
+
+        engine.free_block("heap").unwrap();
+        assert!(engine.is_pointer_dangling(&engine.pointers["p"]));
+        assert!(engine.is_pointer_dangling(&engine.pointers["q"]));
+    }
+
+    #[test]
+    fn type_flow_flags_implicit_conversions() {
+        let typed = |source: &str, target: &str| Pointer3D {
+            type_name: Some(source.to_string()),
+            target_type_name: Some(target.to_string()),
+            ..Pointer3D::default()
+        };
+
+        assert_eq!(type_conversion(&typed("int*", "void*")), Some(("int*", "void*")));
+        assert_eq!(type_conversion(&typed("int*", "int*")), None);
+        assert_eq!(type_conversion(&Pointer3D::default()), None);
+    }
+
+    #[test]
+    fn graph_metrics_count_incoming_pointers() {
+        let mut engine = engine();
+        for (id, x) in [("a", 0.0), ("b", 100.0), ("shared", 300.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "p", "a", "shared");
+        link(&mut engine, "q", "b", "shared");
+
+        let metrics = engine.compute_graph_metrics();
+        assert_eq!(metrics.blocks["shared"].in_degree, 2);
+        assert_eq!(metrics.blocks["a"].out_degree, 1);
+        assert_eq!(metrics.blocks["a"].in_degree, 0);
+    }
+
+    #[test]
+    fn address_ruler_labels_a_tick_every_spacing() {
+        let mut engine = engine();
+        engine.show_address_ruler(0x1000, 1.0);
+        let ruler = engine.address_ruler.unwrap();
+
+        let ticks = engine.ruler_ticks(&ruler, 800.0);
+        assert_eq!(ticks.len(), (800.0 / RULER_TICK_SPACING) as usize + 1);
+        assert_eq!(ticks[0], (0.0, 0x1000));
+        assert_eq!(ticks[1], (RULER_TICK_SPACING, 0x1000 + RULER_TICK_SPACING as u64));
+    }
+
+    #[test]
+    fn object_png_is_cropped_to_the_projected_block_plus_padding() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 40.0, 20.0));
+        let (x, y, width, height) = engine.object_rect("a").unwrap();
+
+        let crop = engine.object_crop("a", 8.0).unwrap();
+        assert_eq!((crop.width, crop.height), ((width + 16.0).ceil() as u32, (height + 16.0).ceil() as u32));
+        assert_eq!((crop.offset_x, crop.offset_y), (8.0 - x, 8.0 - y));
+        assert_eq!(engine.object_crop("missing", 8.0), None);
+    }
+
+    #[test]
+    fn batched_mutations_render_once_at_the_end() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.dirty = false;
+
+        engine.begin_batch();
+        for i in 0..50 {
+            engine.move_memory_block("a", i as f64, 0.0, 0.0).unwrap();
+            assert!(!engine.needs_render());
+        }
+        assert!(engine.dirty);
+        engine.end_batch().unwrap();
+        assert!(engine.needs_render());
+    }
+
+    #[test]
+    fn null_dereference_is_recorded_without_a_target_flash() {
+        let mut engine = engine();
+        engine.insert_block(block("home", 0.0, 0.0));
+        engine.insert_block(block("heap", 200.0, 0.0));
+        link(&mut engine, "valid", "home", "heap");
+        engine.insert_pointer(Pointer3D { id: "nullptr".to_string(), is_null: true, ..Pointer3D::default() });
+
+        assert!(!engine.animate_dereference("nullptr", 300.0).unwrap());
+        assert_eq!(engine.null_derefs.len(), 1);
+        assert_eq!((engine.null_derefs[0].pointer_id.as_str(), engine.null_derefs[0].reason), ("nullptr", "null"));
+        assert!(engine.block_flashes.is_empty());
+
+        assert!(engine.animate_dereference("valid", 300.0).unwrap());
+        assert!(engine.block_flashes.contains_key("heap"));
+        assert_eq!(engine.null_derefs.len(), 1);
+    }
+
+    #[test]
+    fn blocks_sort_by_size_smallest_first() {
+        let mut engine = engine();
+        for (id, size) in [("medium", 8), ("large", 64), ("small", 1)] {
+            engine.insert_block(MemoryBlock3D { size_bytes: Some(size), ..block(id, 0.0, 0.0) });
+        }
+        engine.insert_block(block("unsized", 0.0, 0.0));
+
+        assert_eq!(engine.sorted_block_ids("size_bytes", true).unwrap(), ["small", "medium", "large", "unsized"]);
+        assert_eq!(engine.sorted_block_ids("size_bytes", false).unwrap(), ["large", "medium", "small", "unsized"]);
+        assert!(engine.sorted_block_ids("colour", true).is_err());
+    }
+
+    #[test]
+    fn focus_badges_follow_the_select_next_cycle() {
+        let mut engine = engine();
+        for (id, x, y) in [("third", 0.0, 400.0), ("first", 0.0, 0.0), ("second", 300.0, 0.0)] {
+            engine.insert_block(block(id, x, y));
+        }
+        engine.show_focus_order(true);
+
+        // La insignia i-ésima (i + 1) se dibuja sobre focus_ids()[i]
+        let badges = engine.focus_ids();
+        assert_eq!(badges, ["first", "second", "third"]);
+        let visited: Vec<String> = (0..3).map(|_| engine.select_next().unwrap()).collect();
+        assert_eq!(visited, badges);
+        assert_eq!(engine.select_next().as_ref(), badges.first());
+        assert!(engine.dirty);
+    }
+
+    #[test]
+    fn custom_projection_matrices_transform_coordinates() {
+        let mut engine = engine();
+        engine.projection = projection_from_coefficients(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0]).unwrap();
+        assert_eq!(engine.project(30.0, 40.0, 25.0), (30.0, 40.0));
+
+        // Cizalla horizontal: x' = x + 0.5 * y
+        engine.projection = projection_from_coefficients(&[1.0, 0.5, 0.0, 0.0, 1.0, 0.0]).unwrap();
+        assert_eq!(engine.project(30.0, 40.0, 25.0), (50.0, 40.0));
+
+        assert!(projection_from_coefficients(&[1.0, 0.0]).is_err());
+        assert!(projection_from_coefficients(&[f64::NAN, 0.0, 0.0, 0.0, 1.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn deeper_indirection_bends_further() {
+        let mut engine = engine();
+        for (id, x) in [("pp_home", 0.0), ("p_home", 200.0), ("x", 400.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "pp", "pp_home", "p_home");
+        link(&mut engine, "p", "p_home", "x");
+        engine.show_indirection_bend(true);
+
+        let bend = |id: &str| {
+            let pointer = &engine.pointers[id];
+            let start = engine.project(pointer.start_x, pointer.start_y, pointer.start_z);
+            let end = engine.project(pointer.end_x, pointer.end_y, pointer.end_z);
+            let (cx, cy) = engine.curve_control_point(&engine.projection, pointer, start, end).unwrap();
+            ((cx - (start.0 + end.0) / 2.0).powi(2) + (cy - (start.1 + end.1) / 2.0).powi(2)).sqrt()
+        };
+        assert_eq!(engine.indirection_depth(&engine.pointers["pp"]), 2);
+        assert!(bend("pp") > bend("p"), "depth-2 bend {} vs depth-1 bend {}", bend("pp"), bend("p"));
+    }
+
+    #[test]
+    fn replayed_gestures_end_on_the_recorded_camera() {
+        let mut recorder = engine();
+        recorder.start_gesture_recording();
+        recorder.set_manual_time(100.0);
+        recorder.pan_by(40.0, -20.0);
+        recorder.set_manual_time(250.0);
+        recorder.zoom_at(200.0, 150.0, 1.5);
+        let recorded_end = recorder.camera;
+        let json = recorder.stop_gesture_recording();
+
+        let mut player = engine();
+        player.replay_gestures(&json, 1.0).unwrap();
+        for time_ms in [50.0, 150.0, 300.0] {
+            advance(&mut player, time_ms);
+        }
+        assert!(!player.is_replaying_gestures());
+        assert_eq!(player.camera, recorded_end);
+    }
+
+    #[test]
+    fn linked_list_nodes_get_increasing_depths() {
+        let mut engine = engine();
+        for (id, x) in [("head", 0.0), ("middle", 200.0), ("tail", 400.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "head.next", "head", "middle");
+        link(&mut engine, "middle.next", "middle", "tail");
+
+        engine.update_node_depths("head").unwrap();
+        let depths: Vec<(&str, usize)> = engine.node_depths.iter().map(|(id, depth)| (id.as_str(), *depth)).collect();
+        assert_eq!(depths, [("head", 0), ("middle", 1), ("tail", 2)]);
+    }
+
+    #[test]
+    fn danger_zone_sits_against_the_right_edge() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { width: 160.0, ..block("array", 0.0, 0.0) });
+        engine.show_array_bounds("array", 4).unwrap();
+
+        let rect = engine.block_rect(&engine.memory_blocks["array"]);
+        let (zone_x, zone_y, zone_width, zone_height) = danger_zone_rect(rect, engine.array_bounds["array"]);
+        assert_eq!(zone_x, rect.0 + rect.2);
+        assert_eq!((zone_y, zone_height), (rect.1, rect.3));
+        assert_eq!(zone_width, rect.2 / 4.0 * DANGER_ZONE_SLOTS);
+    }
+
+    #[test]
+    fn rapid_events_are_coalesced_into_one_delivery() {
+        let mut engine = engine();
+        let deliveries = record_events(&mut engine);
+        engine.set_event_coalescing(100.0);
+
+        for i in 0..20 {
+            engine.set_manual_time(i as f64 * 2.0);
+            engine.add_block(block(&format!("b{}", i), 0.0, 0.0));
+        }
+        assert!(deliveries.borrow().is_empty());
+
+        advance(&mut engine, 150.0);
+        let deliveries = deliveries.borrow();
+        assert_eq!(deliveries.len(), 1);
+        let batch: Vec<serde_json::Value> = serde_json::from_str(&deliveries[0]).unwrap();
+        assert_eq!(batch.len(), 20);
+        assert!(batch.iter().all(|event| event["type"] == "block_added"));
+        assert_eq!(batch[19]["id"], "b19");
+    }
+
+    #[test]
+    fn every_change_path_emits_its_event() {
+        let mut engine = engine();
+        let deliveries = record_events(&mut engine);
+        let kinds = |deliveries: &std::cell::RefCell<Vec<String>>| -> Vec<String> {
+            let events: Vec<String> = deliveries
+                .borrow()
+                .iter()
+                .map(|json| {
+                    let event: serde_json::Value = serde_json::from_str(json).unwrap();
+                    format!("{} {}", event["type"].as_str().unwrap(), event["id"].as_str().unwrap())
+                })
+                .collect();
+            deliveries.borrow_mut().clear();
+            events
+        };
+
+        engine.add_block(block("a", 0.0, 0.0));
+        engine.add_block(block("b", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        assert_eq!(kinds(&deliveries), ["block_added a", "block_added b", "pointer_added p"]);
+
+        engine.select_next();
+        assert_eq!(kinds(&deliveries), ["selection_changed a"]);
+
+        engine.group_pointers("all", vec!["p".to_string()]).unwrap();
+        engine.remove_group("all").unwrap();
+        assert_eq!(kinds(&deliveries), ["pointer_removed p"]);
+
+        engine.import_scene(&engine.export_scene()).unwrap();
+        assert_eq!(kinds(&deliveries), ["block_removed a", "block_removed b", "selection_changed ", "block_added a", "block_added b"]);
+
+        engine.reset();
+        assert_eq!(kinds(&deliveries), ["block_removed a", "block_removed b"]);
+    }
+
+    #[test]
+    fn reachable_highlight_covers_the_whole_chain() {
+        let mut engine = engine();
+        for (id, x) in [("root", 0.0), ("node", 200.0), ("leaf", 400.0), ("other", 600.0)] {
+            engine.insert_block(block(id, x, 0.0));
+        }
+        link(&mut engine, "start", "root", "node");
+        link(&mut engine, "node.next", "node", "leaf");
+        link(&mut engine, "unrelated", "other", "root");
+
+        assert_eq!(engine.highlight_reachable("start").unwrap(), ["leaf", "node", "node.next", "start"]);
+        assert!(engine.is_highlighted("node.next") && engine.is_highlighted("leaf"));
+        assert!(!engine.is_highlighted("root") && !engine.is_highlighted("unrelated"));
+    }
+
+    #[test]
+    fn non_finite_coordinates_are_reported_on_import() {
+        let mut engine = engine();
+        engine.insert_block(block("kept", 0.0, 0.0));
+
+        let broken = MemoryBlock3D { x: f64::INFINITY, ..block("broken", 0.0, 0.0) };
+        assert_eq!(
+            ensure_finite(std::iter::empty(), [&broken].into_iter()),
+            Err(EngineError("Non-finite coordinates in: broken".to_string()))
+        );
+
+        // JSON no tiene Infinity: un número desbordado tampoco llega a la escena
+        let scene = engine.export_scene().replace(r#""x":0.0"#, r#""x":1e999"#);
+        assert!(scene.contains("1e999"));
+        assert!(engine.load_scene(&scene).is_err());
+        assert!(engine.memory_blocks["kept"].x.is_finite());
+    }
+
+    #[test]
+    fn views_project_the_same_block_through_their_own_camera() {
+        let mut engine = engine();
+        engine.insert_block(block("shared", 50.0, 30.0));
+        let overview = engine.add_view((Camera { zoom: 0.5, pan_x: 0.0, pan_y: 0.0 }, None));
+        let detail = engine.add_view((Camera { zoom: 2.0, pan_x: -40.0, pan_y: 10.0 }, Some(ORTHOGRAPHIC_PROJECTION)));
+
+        let (x, y, z) = block_anchor(&engine.memory_blocks["shared"]);
+        let in_overview = engine.view_point(&overview, x, y, z).unwrap();
+        let in_detail = engine.view_point(&detail, x, y, z).unwrap();
+        assert_ne!(in_overview, in_detail);
+        assert_eq!(in_detail, (90.0 * 2.0 - 40.0, 50.0 * 2.0 + 10.0));
+    }
+
+    #[test]
+    fn layout_hash_ignores_values_but_tracks_moves() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 120.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        let initial = engine.layout_hash();
+
+        engine.memory_blocks.get_mut("a").unwrap().value = Some("42".to_string());
+        assert_eq!(engine.layout_hash(), initial);
+
+        engine.move_memory_block("b", 200.0, 0.0, 0.0).unwrap();
+        assert_ne!(engine.layout_hash(), initial);
+    }
+
+    #[test]
+    fn control_point_curves_the_pointer_and_orients_the_arrowhead() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        engine.set_control_point("p", 100.0, -80.0, 0.0).unwrap();
+
+        let pointer = &engine.pointers["p"];
+        let start = apply_projection(&engine.projection, pointer.start_x, pointer.start_y, pointer.start_z);
+        let end = apply_projection(&engine.projection, pointer.end_x, pointer.end_y, pointer.end_z);
+        let control = engine.curve_control_point(&engine.projection, pointer, start, end).unwrap();
+        assert_eq!(control, apply_projection(&engine.projection, 100.0, -80.0, 0.0));
+
+        // La bisectriz de las alas apunta en la dirección de la tangente final (control -> fin)
+        let [left, right] = arrow_head_points(end.0, end.1, control.0, control.1);
+        let (base_x, base_y) = ((left.0 + right.0) / 2.0, (left.1 + right.1) / 2.0);
+        let head_angle = (end.1 - base_y).atan2(end.0 - base_x);
+        let tangent_angle = (end.1 - control.1).atan2(end.0 - control.0);
+        assert!((head_angle - tangent_angle).abs() < 1e-9);
+        assert!((head_angle - (end.1 - start.1).atan2(end.0 - start.0)).abs() > 1e-3);
+    }
+
+    #[test]
+    fn auto_color_gives_each_colorless_block_its_own_color() {
+        let mut engine = engine();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            engine.insert_block(MemoryBlock3D { color: String::new(), ..block(id, i as f64 * 100.0, 0.0) });
+        }
+        engine.insert_block(block("styled", 300.0, 0.0));
+
+        assert_eq!(engine.auto_color_unset(), 3);
+        let colors: std::collections::BTreeSet<&str> =
+            ["a", "b", "c"].iter().map(|id| engine.memory_blocks[*id].color.as_str()).collect();
+        assert_eq!(colors.len(), 3);
+        assert!(colors.iter().all(|color| !color.is_empty()));
+        assert_eq!(engine.memory_blocks["styled"].color, "#4488ff");
+    }
+
+    #[test]
+    fn move_value_transfers_the_value_and_leaves_the_source_moved_from() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { value: Some("\"hello\"".to_string()), ..block("src", 0.0, 0.0) });
+        engine.insert_block(block("dst", 120.0, 0.0));
+
+        engine.move_value("src", "dst").unwrap();
+        assert_eq!(engine.memory_blocks["dst"].value.as_deref(), Some("\"hello\""));
+        assert!(!engine.memory_blocks["dst"].moved_from);
+        assert_eq!(engine.memory_blocks["src"].value, None);
+        assert!(engine.is_moved_from("src").unwrap());
+    }
+
+    #[test]
+    fn memory_budget_drops_the_oldest_entries() {
+        let mut engine = engine();
+        for i in 0..20 {
+            engine.set_manual_time(i as f64 * 10.0);
+            engine.record_trace("a", Some("b"), format!("step-{i:02}"), false);
+        }
+        let entry_bytes = engine.trace.iter().map(trace_entry_bytes).max().unwrap();
+
+        let budget = entry_bytes * 3;
+        engine.set_memory_budget(budget);
+        assert!(engine.retained_bytes() <= budget);
+        let labels: Vec<&str> = engine.trace.iter().map(|entry| entry.label.as_str()).collect();
+        assert_eq!(labels, ["step-17", "step-18", "step-19"]);
+    }
+
+    #[test]
+    fn pending_pointer_ends_in_a_question_mark_until_retargeted() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", 0.0, 0.0));
+        engine.insert_block(block("target", 200.0, 0.0));
+        link(&mut engine, "p", "owner", "target");
+        let pointer = engine.pointers.get_mut("p").unwrap();
+        pointer.pending = true;
+        pointer.target_block_id = None;
+
+        let pointer = &engine.pointers["p"];
+        assert_eq!(engine.target_of(pointer), None);
+        let start = engine.project(pointer.start_x, pointer.start_y, pointer.start_z);
+        let ((tip_x, tip_y), mark) = pending_terminus(start, (start.0 + 100.0, start.1));
+        assert_eq!((tip_x, tip_y), (start.0 + PENDING_ARROW_LENGTH, start.1));
+        assert_eq!(mark, (tip_x, tip_y + 6.0));
+        assert_eq!(engine.export_svg(400.0, 300.0).matches("<path").count(), 1);
+
+        engine.retarget_pointer("p", "target").unwrap();
+        let pointer = &engine.pointers["p"];
+        assert!(!pointer.pending);
+        assert_eq!(engine.target_of(pointer), Some("target"));
+        assert_eq!((pointer.end_x, pointer.end_y, pointer.end_z), block_anchor(&engine.memory_blocks["target"]));
+        // Línea más cabeza de flecha
+        assert_eq!(engine.export_svg(400.0, 300.0).matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn scene_stats_tally_blocks_pointers_and_leaks() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D {
+            memory_type: "stack".to_string(),
+            frame_id: Some("main".to_string()),
+            ..block("local", 0.0, 0.0)
+        });
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(16), ..block("node", 100.0, 0.0) });
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(32), ..block("orphan", 200.0, 0.0) });
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(64), freed_at: Some(5.0), ..block("freed", 300.0, 0.0) });
+        link(&mut engine, "p", "local", "node");
+        link(&mut engine, "q", "node", "freed");
+
+        let stats = engine.stats();
+        assert_eq!(stats.blocks_by_type["heap"], 3);
+        assert_eq!(stats.blocks_by_type["stack"], 1);
+        assert_eq!(stats.pointers_by_kind.values().sum::<usize>(), 2);
+        assert_eq!(stats.dangling_pointers, 1);
+        assert_eq!(stats.null_pointers, 0);
+        assert_eq!(stats.leaked_blocks, 1);
+        assert_eq!(stats.total_allocated_bytes, 48);
+        assert_eq!(stats.stack_depth, 1);
+    }
+
+    #[test]
+    fn color_tween_blends_halfway_through() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { color: "#000000".to_string(), ..block("a", 0.0, 0.0) });
+        engine.set_color_transition(1000.0);
+        engine.set_color("a", "#ff8040").unwrap();
+
+        advance(&mut engine, 500.0);
+        assert_eq!(engine.get_color("a").unwrap(), "rgb(128, 64, 32)");
+
+        advance(&mut engine, 1000.0);
+        assert_eq!(engine.get_color("a").unwrap(), "#ff8040");
+    }
+
+    #[test]
+    fn stack_pointer_into_the_heap_is_a_storage_crossing() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { memory_type: "stack".to_string(), ..block("local", 0.0, 0.0) });
+        engine.insert_block(block("node", 100.0, 0.0));
+        engine.insert_block(block("next", 200.0, 0.0));
+        link(&mut engine, "p", "local", "node");
+        link(&mut engine, "q", "node", "next");
+
+        let crossings = engine.storage_crossings();
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].pointer_id, "p");
+        assert_eq!(crossings[0].storage_block_id, "local");
+        assert_eq!(crossings[0].target_block_id, "node");
+        assert_eq!(crossings[0].crossing, "stack→heap");
+    }
+
+    #[test]
+    fn references_cannot_be_retargeted() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 100.0, 0.0));
+        engine.insert_block(block("c", 200.0, 0.0));
+        link(&mut engine, "r", "a", "b");
+        engine.pointers.get_mut("r").unwrap().kind = PointerKind::Reference;
+
+        assert_eq!(engine.retarget("r", "c"), Err(EngineError("References cannot be rebound: r".to_string())));
+        assert_eq!(engine.pointers["r"].target_block_id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn required_canvas_size_encloses_the_scene_plus_padding() {
+        let mut engine = engine();
+        engine.insert_block(block("a", -50.0, 20.0));
+        engine.insert_block(block("b", 300.0, 140.0));
+        link(&mut engine, "p", "a", "b");
+        engine.set_control_point("p", 100.0, -200.0, 0.0).unwrap();
+
+        let padding = 24.0;
+        let size = engine.canvas_size(padding);
+        let (min_x, min_y, max_x, max_y) = engine.scene_bounds().unwrap();
+        assert_eq!(size.width, (max_x - min_x + 2.0 * padding).ceil());
+        assert_eq!(size.height, (max_y - min_y + 2.0 * padding).ceil());
+
+        // Con el pan sugerido toda la geometría queda dentro del margen
+        for block in engine.memory_blocks.values() {
+            let (x, y, w, h) = engine.block_rect(block);
+            assert!(x + size.pan_x >= padding && x + w + size.pan_x <= size.width - padding);
+            assert!(y + size.pan_y >= padding && y + h + size.pan_y <= size.height - padding);
+        }
+        let (_, control_y) = engine.project(100.0, -200.0, 0.0);
+        assert_eq!(control_y + size.pan_y, padding);
+    }
+
+    #[test]
+    fn weight_label_sits_mid_curve_and_scales_the_line() {
+        assert_eq!(curve_midpoint((0.0, 0.0), None, (100.0, 40.0)), (50.0, 20.0));
+        assert_eq!(curve_midpoint((0.0, 0.0), Some((50.0, -100.0)), (100.0, 0.0)), (50.0, -50.0));
+
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 100.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        engine.set_weight("p", Some(4.0)).unwrap();
+        let nominal = engine.pointers["p"].thickness;
+        assert_eq!(engine.line_thickness(&engine.pointers["p"]), nominal);
+
+        engine.set_thickness_by_weight(true);
+        assert_eq!(engine.line_thickness(&engine.pointers["p"]), 4.0 * WEIGHT_THICKNESS_SCALE);
+        engine.set_weight("p", Some(100.0)).unwrap();
+        assert_eq!(engine.line_thickness(&engine.pointers["p"]), MAX_WEIGHTED_THICKNESS);
+    }
+
+    #[test]
+    fn pointer_into_its_own_block_is_flagged() {
+        let mut engine = engine();
+        engine.insert_block(block("node", 0.0, 0.0));
+        engine.insert_block(block("other", 100.0, 0.0));
+        link(&mut engine, "self_ptr", "node", "node");
+        link(&mut engine, "next", "node", "other");
+
+        let flagged = engine.self_references();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].pointer_id, "self_ptr");
+        assert_eq!(flagged[0].block_id, "node");
+        assert_eq!(flagged[0].kind, "pointer");
+    }
+
+    #[test]
+    fn ease_in_out_is_symmetric_around_the_midpoint() {
+        let samples = [0.0, 0.25, 0.5, 0.75, 1.0].map(|t| Easing::EaseInOut.apply(t));
+        assert_eq!(samples, [0.0, 0.125, 0.5, 0.875, 1.0]);
+    }
+
+    #[test]
+    fn split_halves_load_their_own_scenes() {
+        let mut before = engine();
+        before.insert_block(block("a", 0.0, 0.0));
+        before.insert_block(block("b", 100.0, 0.0));
+        let mut after = engine();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            after.insert_block(block(id, i as f64 * 100.0, 0.0));
+        }
+        link(&mut after, "p", "a", "c");
+
+        let mut host = engine();
+        host.projection = ORTHOGRAPHIC_PROJECTION;
+        let left = host.split_engine(&before.export_scene()).unwrap();
+        let right = host.split_engine(&after.export_scene()).unwrap();
+        assert_eq!((left.memory_blocks.len(), left.pointers.len()), (2, 0));
+        assert_eq!((right.memory_blocks.len(), right.pointers.len()), (3, 1));
+        assert_eq!(right.projection, ORTHOGRAPHIC_PROJECTION);
+        assert!(host.memory_blocks.is_empty());
+    }
+
+    #[test]
+    fn pinned_pointer_ignores_retargeting() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("b", 100.0, 0.0));
+        engine.insert_block(block("c", 200.0, 0.0));
+        link(&mut engine, "p", "a", "b");
+        engine.pin_pointer("p").unwrap();
+        let authored = engine.pointers["p"].clone();
+
+        engine.retarget_pointer("p", "c").unwrap();
+        engine.animate_pointer_to("p", "c", 300.0).unwrap();
+        advance(&mut engine, 300.0);
+
+        let pointer = &engine.pointers["p"];
+        assert_eq!(pointer.target_block_id.as_deref(), Some("b"));
+        assert_eq!((pointer.end_x, pointer.end_y, pointer.end_z), (authored.end_x, authored.end_y, authored.end_z));
+
+        engine.unpin_pointer("p").unwrap();
+        engine.retarget_pointer("p", "c").unwrap();
+        assert_eq!(engine.pointers["p"].target_block_id.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn sequence_diagram_lists_dereferences_in_order() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { memory_type: "stack".to_string(), ..block("main", 0.0, 0.0) });
+        engine.insert_block(block("node", 100.0, 0.0));
+        engine.insert_block(block("tail", 200.0, 0.0));
+        link(&mut engine, "head", "main", "node");
+        link(&mut engine, "next", "node", "tail");
+
+        assert!(engine.animate_dereference("head", 100.0).unwrap());
+        advance(&mut engine, 10.0);
+        assert!(engine.animate_dereference("next", 100.0).unwrap());
+
+        assert_eq!(
+            engine.export_sequence_diagram(),
+            [
+                "sequenceDiagram",
+                "    participant main",
+                "    participant node",
+                "    participant tail",
+                "    main->>node: *head",
+                "    node->>tail: *next",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn inference_radius_decides_whether_a_near_miss_targets_the_block() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", -300.0, 0.0));
+        engine.insert_block(block("target", 0.0, 0.0));
+        link(&mut engine, "p", "owner", "target");
+        let pointer = engine.pointers.get_mut("p").unwrap();
+        pointer.target_block_id = None;
+        pointer.end_x = 84.0;
+        pointer.end_y = 20.0;
+        pointer.end_z = 0.0;
+
+        let (x, y) = engine.project(84.0, 20.0, 0.0);
+        let miss = distance_to_rect(x, y, engine.block_rect(&engine.memory_blocks["target"]));
+        assert!(miss > 0.0 && miss < 10.0);
+
+        engine.set_target_inference_radius(0.0);
+        assert_eq!(engine.pointers_targeting("target").count(), 0);
+        engine.set_target_inference_radius(10.0);
+        let ids: Vec<&str> = engine.pointers_targeting("target").map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["p"]);
+    }
+
+    #[test]
+    fn gc_sweeps_unreachable_blocks_and_keeps_reachable_and_locked_ones() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { memory_type: "stack".to_string(), ..block("root", 0.0, 0.0) });
+        for (i, id) in ["live", "garbage", "pinned_garbage"].iter().enumerate() {
+            engine.insert_block(block(id, 100.0 * (i + 1) as f64, 0.0));
+        }
+        link(&mut engine, "p", "root", "live");
+        engine.set_locked("pinned_garbage", true).unwrap();
+
+        let marked = engine.gc_mark(&["root".to_string()], 0.0).unwrap();
+        assert_eq!(marked, ["live", "root"]);
+        assert_eq!(engine.gc_sweep(0.0).unwrap(), ["garbage"]);
+
+        let mut remaining: Vec<&str> = engine.memory_blocks.keys().map(String::as_str).collect();
+        remaining.sort();
+        assert_eq!(remaining, ["live", "pinned_garbage", "root"]);
+        assert!(engine.gc_sweep(0.0).is_err());
+    }
+
+    #[test]
+    fn pointer_between_arenas_is_a_region_crossing() {
+        let mut engine = engine();
+        for (i, id) in ["a1", "a2", "b1"].iter().enumerate() {
+            engine.insert_block(block(id, 100.0 * i as f64, 0.0));
+        }
+        engine.set_region("a1", Some("arena_a".to_string())).unwrap();
+        engine.set_region("a2", Some("arena_a".to_string())).unwrap();
+        engine.set_region("b1", Some("arena_b".to_string())).unwrap();
+        link(&mut engine, "local", "a1", "a2");
+        link(&mut engine, "remote", "a2", "b1");
+
+        let crossings = engine.cross_region_pointers();
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].pointer_id, "remote");
+        assert_eq!((crossings[0].source_region.as_str(), crossings[0].target_region.as_str()), ("arena_a", "arena_b"));
+    }
+
+    #[test]
+    fn anchored_pointer_start_follows_its_source_block() {
+        let mut engine = engine();
+        engine.insert_block(block("var", 0.0, 0.0));
+        engine.insert_block(block("target", 200.0, 0.0));
+        link(&mut engine, "p", "var", "target");
+        engine.set_source_block("p", None).unwrap();
+        engine.pointers.get_mut("p").unwrap().start_x = -500.0;
+        engine.set_source_block("p", Some("var".to_string())).unwrap();
+        let pointer = &engine.pointers["p"];
+        assert_eq!((pointer.start_x, pointer.start_y, pointer.start_z), block_anchor(&engine.memory_blocks["var"]));
+
+        engine.move_memory_block("var", 40.0, 60.0, 0.0).unwrap();
+        let pointer = &engine.pointers["p"];
+        assert_eq!((pointer.start_x, pointer.start_y, pointer.start_z), block_anchor(&engine.memory_blocks["var"]));
+        assert_eq!((pointer.end_x, pointer.end_y, pointer.end_z), block_anchor(&engine.memory_blocks["target"]));
+    }
+
+    #[test]
+    fn utilization_bar_is_half_filled_by_half_the_arena() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(16), address: Some(0x1000), ..block("a", 0.0, 0.0) });
+        engine.insert_block(MemoryBlock3D {
+            size_bytes: Some(48),
+            address: Some(0x1010),
+            color: "#00ff00".to_string(),
+            ..block("b", 100.0, 0.0)
+        });
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(64), freed_at: Some(1.0), ..block("freed", 200.0, 0.0) });
+
+        let (segments, fraction) = engine.utilization_segments(200.0, 128.0);
+        assert_eq!(fraction, 0.5);
+        assert_eq!(segments, [(0.0, 25.0, "#4488ff"), (25.0, 100.0, "#00ff00")]);
+    }
+
+    #[test]
+    fn pointer_arithmetic_advances_by_the_stride() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(32), address: Some(0x2000), ..block("array", 0.0, 0.0) });
+        engine.insert_block(block("owner", -200.0, 0.0));
+        link(&mut engine, "p", "owner", "array");
+        let pointer = engine.pointers.get_mut("p").unwrap();
+        pointer.stride_bytes = Some(4);
+        pointer.target_address = Some(0x2000);
+        let start_x = pointer.end_x;
+
+        engine.animate_pointer_arithmetic("p", 2, 100.0).unwrap();
+        assert_eq!(engine.pointers["p"].target_address, Some(0x2008));
+        assert_eq!(engine.stride_indicators["p"], 8);
+
+        advance(&mut engine, 100.0);
+        let pointer = &engine.pointers["p"];
+        assert!(!engine.stride_indicators.contains_key("p"));
+        // 8 de 32 bytes: una cuarta parte del ancho del bloque
+        assert_eq!(pointer.end_x - start_x, 20.0);
+    }
+
+    #[test]
+    fn viewport_capture_only_frames_the_visible_block() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { color: "#aa0000".to_string(), ..block("left", 0.0, 0.0) });
+        engine.insert_block(MemoryBlock3D { color: "#00aa00".to_string(), ..block("right", 1000.0, 0.0) });
+        engine.insert_block(block("far", 0.0, 800.0));
+        link(&mut engine, "into_view", "left", "right");
+        link(&mut engine, "hidden", "left", "far");
+        let (x, y, _, _) = engine.block_rect(&engine.memory_blocks["right"]);
+        engine.camera = Camera { zoom: 1.0, pan_x: 20.0 - x, pan_y: 20.0 - y };
+        let measure = |text: &str, _: &str| Ok(TextExtent { left: 0.0, right: text.len() as f64 * 8.0, ascent: 11.0, descent: 3.0 });
+
+        // export_viewport_png dibuja exactamente este conjunto: el bloque enfocado y el puntero que llega a él
+        let framed = engine.objects_in_viewport(300.0, 200.0, measure).unwrap();
+        assert_eq!(framed.into_iter().collect::<Vec<_>>(), ["into_view", "right"]);
+
+        // Un valor largo del bloque de la izquierda llega a asomar en el encuadre
+        engine.memory_blocks.get_mut("left").unwrap().value = Some("x".repeat(200));
+        assert!(engine.objects_in_viewport(300.0, 200.0, measure).unwrap().contains("left"));
+    }
+
+    #[test]
+    fn pointer_takes_its_target_color_when_matching() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", 0.0, 0.0));
+        engine.insert_block(MemoryBlock3D { color: "#0000ff".to_string(), ..block("blue", 200.0, 0.0) });
+        engine.insert_block(MemoryBlock3D { freed_at: Some(1.0), ..block("freed", 400.0, 0.0) });
+        link(&mut engine, "p", "owner", "blue");
+        link(&mut engine, "stale", "owner", "freed");
+        assert_eq!(engine.pointer_colors(&engine.pointers["p"]).0, "#ff0000");
+
+        engine.match_target_color(true);
+        let (line, head) = engine.pointer_colors(&engine.pointers["p"]);
+        assert_eq!(line, "#0000ff");
+        assert_eq!(head, shade_color("#0000ff", 0.75));
+        assert_ne!(head, line);
+        assert_eq!(engine.pointer_colors(&engine.pointers["stale"]).0, DANGLING_POINTER_COLOR);
+        assert!(engine.export_svg(800.0, 400.0).contains("stroke=\"#0000ff\""));
+    }
+
+    #[test]
+    fn diagnostics_report_dangling_pointers_and_leaks_by_severity() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { memory_type: "stack".to_string(), ..block("main", 0.0, 0.0) });
+        engine.insert_block(MemoryBlock3D { freed_at: Some(1.0), ..block("freed", 100.0, 0.0) });
+        engine.insert_block(block("lost", 200.0, 0.0));
+        link(&mut engine, "stale", "main", "freed");
+
+        let found: Vec<(Severity, &str, Vec<String>)> =
+            engine.collect_diagnostics().into_iter().map(|d| (d.severity, d.code, d.object_ids)).collect();
+        assert_eq!(
+            found,
+            [
+                (Severity::Error, "dangling_pointer", vec!["stale".to_string(), "freed".to_string()]),
+                (Severity::Warning, "leaked_block", vec!["lost".to_string()]),
+                (Severity::Info, "storage_class_crossing", vec!["stale".to_string()]),
+            ]
+        );
+
+        engine.set_diagnostic_enabled("storage_class_crossing", false);
+        assert_eq!(engine.collect_diagnostics().len(), 2);
+    }
+
+    #[test]
+    fn list_insert_splices_the_new_node_between_its_neighbours() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("c", 200.0, 0.0));
+        link(&mut engine, "a.next", "a", "c");
+
+        engine.list_insert("a", block("b", 100.0, 0.0), "c", 0.0).unwrap();
+        assert_eq!(engine.pointers["a.next"].target_block_id.as_deref(), Some("b"));
+        assert_eq!(engine.pointers["b.next"].target_block_id.as_deref(), Some("c"));
+        assert_eq!(engine.pointers["b.next"].source_block_id.as_deref(), Some("b"));
+        let b_next = &engine.pointers["b.next"];
+        assert_eq!((b_next.end_x, b_next.end_y, b_next.end_z), block_anchor(&engine.memory_blocks["c"]));
+    }
+
+    #[test]
+    fn list_insert_ignores_a_pinned_link_without_touching_the_scene() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("c", 200.0, 0.0));
+        link(&mut engine, "a.next", "a", "c");
+        engine.pin_pointer("a.next").unwrap();
+
+        engine.list_insert("a", block("b", 100.0, 0.0), "c", 0.0).unwrap();
+        assert!(!engine.memory_blocks.contains_key("b"));
+        assert_eq!(engine.pointers.len(), 1);
+        assert_eq!(engine.pointers["a.next"].target_block_id.as_deref(), Some("c"));
+    }
+
+    #[test]
+    fn object_bounds_grow_to_enclose_a_long_label() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { value: Some("0xdeadbeefcafebabe".to_string()), ..block("a", 0.0, 0.0) });
+        engine.camera = Camera { zoom: 2.0, pan_x: 5.0, pan_y: -5.0 };
+        let (x, y, width, height) = engine.object_rect("a").unwrap();
+
+        let bounds = engine
+            .object_footprint("a", |text, font| {
+                assert_eq!(font, "14px Arial");
+                Ok(TextExtent { left: 0.0, right: text.len() as f64 * 8.0, ascent: 11.0, descent: 3.0 })
+            })
+            .unwrap();
+        let label_right = x + BLOCK_LABEL_OFFSET.0 + 18.0 * 8.0;
+        assert!(label_right > x + width);
+        assert_eq!(
+            bounds,
+            ScreenRect {
+                x: x * 2.0 + 5.0,
+                y: y * 2.0 - 5.0,
+                width: (label_right - x) * 2.0,
+                height: height.max(BLOCK_LABEL_OFFSET.1 + 3.0) * 2.0,
+            }
+        );
+    }
+
+    #[test]
+    fn instantiating_a_type_param_makes_a_concrete_typed_block() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { type_param: Some("T".to_string()), ..block("slot", 0.0, 0.0) });
+        assert_eq!(block_label(&engine.memory_blocks["slot"]), Some(("T", "bold 20px Arial")));
+        assert!(engine.export_svg(400.0, 300.0).contains("stroke-dasharray"));
+
+        engine.instantiate_type_param("slot", "int").unwrap();
+        let block = &engine.memory_blocks["slot"];
+        assert_eq!(block.type_param, None);
+        assert_eq!(block.type_name.as_deref(), Some("int"));
+        assert!(engine.block_flashes.contains_key("slot"));
+        assert!(!engine.export_svg(400.0, 300.0).contains("stroke-dasharray"));
+    }
+
+    #[test]
+    fn essential_owners_leave_out_weak_pointers() {
+        let mut engine = engine();
+        for (i, id) in ["a", "b", "c", "shared"].iter().enumerate() {
+            engine.insert_block(block(id, 100.0 * i as f64, 0.0));
+        }
+        for (id, from, kind) in [("sp1", "a", PointerKind::Shared), ("sp2", "b", PointerKind::Shared), ("wp", "c", PointerKind::Weak)] {
+            link(&mut engine, id, from, "shared");
+            engine.pointers.get_mut(id).unwrap().kind = kind;
+        }
+
+        assert_eq!(engine.owners_keeping_alive("shared").unwrap(), ["sp1", "sp2"]);
+    }
+
+    #[test]
+    fn title_is_placed_at_its_position_and_exported_outside_the_scene() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.camera = Camera { zoom: 3.0, pan_x: 50.0, pan_y: 50.0 };
+        engine.set_title(Some("Lists & nodes".to_string()), "bottom-right").unwrap();
+        assert_eq!(engine.title, Some(("Lists & nodes".to_string(), TitlePosition::BottomRight)));
+        assert_eq!(TitlePosition::BottomRight.anchor(400.0, 300.0), (384.0, 284.0, "right"));
+        assert_eq!(TitlePosition::Top.anchor(400.0, 300.0), (200.0, TITLE_MARGIN + TITLE_FONT_SIZE, "center"));
+
+        let svg = engine.export_svg(400.0, 300.0);
+        let title = "<text x=\"384\" y=\"284\" font-weight=\"bold\" font-size=\"20\" font-family=\"Arial\" fill=\"white\" text-anchor=\"end\">Lists &amp; nodes</text>";
+        let position = svg.find(title).unwrap();
+        // Fuera del grupo transformado por la cámara
+        assert!(position > svg.find("</g>").unwrap());
+
+        engine.set_title(None, "top").unwrap();
+        assert!(!engine.export_svg(400.0, 300.0).contains("Lists"));
+    }
+
+    #[test]
+    fn diamond_reports_the_node_reached_by_two_paths() {
+        let mut engine = engine();
+        for (i, id) in ["root", "left", "right", "leaf"].iter().enumerate() {
+            engine.insert_block(block(id, 100.0 * i as f64, 0.0));
+        }
+        link(&mut engine, "root.l", "root", "left");
+        link(&mut engine, "root.r", "root", "right");
+        link(&mut engine, "left.c", "left", "leaf");
+        link(&mut engine, "right.c", "right", "leaf");
+
+        let violations = engine.tree_violations("root").unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].kind, violations[0].block_id.as_str()), ("shared", "leaf"));
+        assert_eq!(violations[0].pointer_ids, ["left.c", "right.c"]);
+    }
+
+    #[test]
+    fn morph_grows_in_the_new_block_while_keeping_the_old_one() {
+        let mut target = engine();
+        target.insert_block(block("a", 0.0, 0.0));
+        target.insert_block(block("b", 150.0, 0.0));
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+
+        engine.animate_morph_to(&target.export_scene(), 1000.0).unwrap();
+        advance(&mut engine, 500.0);
+        let a = &engine.memory_blocks["a"];
+        assert_eq!((a.x, a.y, a.z), (0.0, 0.0, 0.0));
+        assert_eq!(engine.block_growth("a"), 1.0);
+        assert_eq!(engine.block_growth("b"), 0.5);
+        assert_eq!(engine.memory_blocks["b"].x, 150.0);
+
+        advance(&mut engine, 1000.0);
+        assert_eq!(engine.block_growth("b"), 1.0);
+    }
+
+    #[test]
+    fn morph_cancels_an_earlier_slide_of_a_block_it_places() {
+        let scene_with = |x: f64| {
+            let mut scene = engine();
+            scene.insert_block(block("a", x, 0.0));
+            scene.export_scene()
+        };
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.animate_morph_to(&scene_with(200.0), 1000.0).unwrap();
+        advance(&mut engine, 500.0);
+        let halfway = engine.memory_blocks["a"].x;
+        assert!(halfway > 0.0 && halfway < 200.0);
+
+        // Destino igual a la posición actual: el bloque no sigue deslizándose
+        engine.animate_morph_to(&scene_with(halfway), 1000.0).unwrap();
+        advance(&mut engine, 1500.0);
+        assert_eq!(engine.memory_blocks["a"].x, halfway);
+
+        engine.animate_morph_to(&scene_with(200.0), 1000.0).unwrap();
+        advance(&mut engine, 1600.0);
+        engine.animate_morph_to(&scene_with(50.0), 0.0).unwrap();
+        advance(&mut engine, 3000.0);
+        assert_eq!(engine.memory_blocks["a"].x, 50.0);
+    }
+
+    #[test]
+    fn overlap_ratio_clamps_ranges_at_the_top_of_the_address_space() {
+        let mut engine = engine();
+        engine.insert_block(block("owner", 0.0, 0.0));
+        engine.insert_block(MemoryBlock3D {
+            size_bytes: Some(32),
+            address: Some(0xffff_ffff_ffff_fff0),
+            ..block("high", 200.0, 0.0)
+        });
+        link(&mut engine, "p", "owner", "high");
+        link(&mut engine, "q", "owner", "high");
+        engine.pointers.get_mut("q").unwrap().target_range = Some((8, 32));
+
+        // p cubre [fff0, MAX) y q [fff8, MAX)
+        assert_eq!(engine.target_byte_range("p").unwrap(), Some(("high", 0xffff_ffff_ffff_fff0, u64::MAX)));
+        let ratio = engine.target_overlap_ratio("p", "q").unwrap();
+        assert!((ratio - 7.0 / 15.0).abs() < 1e-9, "ratio was {}", ratio);
+    }
+
+    #[test]
+    fn oversized_imported_tag_width_masks_the_whole_address() {
+        assert_eq!(tag_mask(2), 0b11);
+        assert_eq!(tag_mask(63), u64::MAX >> 1);
+        assert_eq!(tag_mask(64), u64::MAX);
+        assert_eq!(tag_mask(200), u64::MAX);
+
+        let mut source = engine();
+        source.insert_pointer(Pointer3D {
+            id: "p".to_string(),
+            target_address: Some(0x1003),
+            tag_bits: Some(1),
+            tag_width: Some(70),
+            ..Pointer3D::default()
+        });
+        let mut engine = engine();
+        engine.load_scene(&source.export_scene()).unwrap();
+        assert_eq!(engine.masked_target_address("p").unwrap(), Some(0));
+    }
+
+    #[test]
+    fn svg_export_keeps_label_fonts_and_pointer_styles_of_the_canvas() {
+        let mut engine = engine();
+        let mut generic = block("generic", 0.0, 0.0);
+        generic.type_param = Some("T".to_string());
+        engine.insert_block(generic);
+        let mut freed = block("freed", 200.0, 0.0);
+        freed.freed_at = Some(0.0);
+        freed.read_only = true;
+        engine.insert_block(freed);
+        engine.insert_block(block("target", 400.0, 0.0));
+        link(&mut engine, "pending", "generic", "target");
+        let pointer = engine.pointers.get_mut("pending").unwrap();
+        pointer.pending = true;
+        pointer.target_block_id = None;
+        link(&mut engine, "alias", "freed", "target");
+        engine.pointers.get_mut("alias").unwrap().kind = PointerKind::Reference;
+
+        let svg = engine.export_svg(800.0, 300.0);
+        // La etiqueta del parámetro de tipo conserva la fuente de block_label
+        assert!(svg.contains("font-weight=\"bold\" font-size=\"20\" font-family=\"Arial\" fill=\"#111111\">T</text>"));
+        // Bloque liberado: atenuado, discontinuo y con el borde de solo lectura
+        assert!(svg.contains("<g opacity=\"0.5\">\n<rect x=\"200\""));
+        assert!(svg.contains("stroke=\"#85C1E9\" stroke-width=\"3\" stroke-dasharray=\"6 4\"/>"));
+
+        // Pendiente: tramo discontinuo hasta pending_terminus y su interrogación
+        let pointer = &engine.pointers["pending"];
+        let start = engine.project(pointer.start_x, pointer.start_y, pointer.start_z);
+        let end = engine.project(pointer.end_x, pointer.end_y, pointer.end_z);
+        let ((tip_x, tip_y), (mark_x, mark_y)) = pending_terminus(start, end);
+        assert!(svg.contains(&format!("L {} {}\" fill=\"none\" stroke=\"#ff0000\" stroke-width=\"3\" stroke-dasharray=\"5 4\"/>", tip_x, tip_y)));
+        assert!(svg.contains(&format!("<text x=\"{}\" y=\"{}\" font-weight=\"bold\" font-size=\"16\"", mark_x, mark_y)));
+
+        // Referencia: dos líneas paralelas y ninguna cabeza de flecha
+        let alias = &engine.pointers["alias"];
+        let start = engine.project(alias.start_x, alias.start_y, alias.start_z);
+        let end = engine.project(alias.end_x, alias.end_y, alias.end_z);
+        let [(a, b), (c, d)] = reference_binding_lines(alias, start, end);
+        let double = format!("M {} {} L {} {} M {} {} L {} {}", a.0, a.1, b.0, b.1, c.0, c.1, d.0, d.1);
+        assert!(svg.contains(&format!("<path d=\"{}\" fill=\"none\" stroke=\"#ff0000\" stroke-width=\"1.5\"/>", double)));
+        assert_eq!(svg.matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn list_insert_reuses_the_next_pointer_declared_by_the_new_node() {
+        let mut engine = engine();
+        engine.insert_block(block("a", 0.0, 0.0));
+        engine.insert_block(block("c", 200.0, 0.0));
+        link(&mut engine, "a.next", "a", "c");
+        link(&mut engine, "b.link", "a", "a");
+        engine.pointers.get_mut("b.link").unwrap().source_block_id = Some("b".to_string());
+
+        engine.list_insert("a", block("b", 100.0, 0.0), "c", 0.0).unwrap();
+        assert!(!engine.pointers.contains_key("b.next"));
+        assert_eq!(engine.pointers["b.link"].target_block_id.as_deref(), Some("c"));
+        assert_eq!(engine.pointers["a.next"].target_block_id.as_deref(), Some("b"));
+        assert_eq!(engine.pointers.len(), 2);
+    }
+
+    #[test]
+    fn pointer_arithmetic_refuses_to_move_a_reference() {
+        let mut engine = engine();
+        engine.insert_block(MemoryBlock3D { size_bytes: Some(32), address: Some(0x2000), ..block("array", 0.0, 0.0) });
+        engine.insert_block(block("owner", -200.0, 0.0));
+        link(&mut engine, "r", "owner", "array");
+        let reference = engine.pointers.get_mut("r").unwrap();
+        reference.kind = PointerKind::Reference;
+        reference.target_address = Some(0x2000);
+        let end_x = reference.end_x;
+
+        let result = engine.pointer_arithmetic("r", 1, 0.0);
+        assert_eq!(result, Err(EngineError("References cannot be rebound: r".to_string())));
+        assert_eq!(engine.pointers["r"].target_address, Some(0x2000));
+        assert_eq!(engine.pointers["r"].end_x, end_x);
+        assert!(!engine.stride_indicators.contains_key("r"));
+    }
 }